@@ -0,0 +1,300 @@
+//! Derive macros for the dirty crate, use them through the `derive` feature
+//! of dirty rather than depending on this crate directly.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{parse_macro_input, Data, DataEnum, DeriveInput, Error, Fields, Index, Member};
+
+/// Generates a `Dirty<Name>` tracker type for a struct with named fields,
+/// holding the struct alongside one dirty flag per field.
+///
+/// For each field `foo` the tracker gets `set_foo()` and `foo_mut()` which set
+/// only that field's flag, and `foo_dirty()` to check it. The tracker derefs
+/// to the struct and implements `dirty::DirtyFields` and `dirty::StructFields`.
+///
+/// For an enum the tracker has a flag for the variant and one per field of
+/// every variant, named `Variant.field` in `FIELDS`. `set()` and `write()`
+/// mark the whole value dirty, as they may switch variants, while
+/// `variant_field_mut()` marks only that field if the variant is current.
+/// Enum trackers don't implement `dirty::StructFields`, so the struct output
+/// formats such as merge patches are not available for them.
+#[proc_macro_derive(DirtyFields)]
+pub fn derive_dirty_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+fn expand(input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    if let Data::Enum(ref data) = input.data {
+        return expand_enum(&input, data);
+    }
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(Error::new(Span::call_site(),
+                                      "DirtyFields requires named fields"))
+            }
+        },
+        _ => return Err(Error::new(Span::call_site(), "DirtyFields requires a struct or enum")),
+    };
+    if fields.len() > 64 {
+        return Err(Error::new(Span::call_site(),
+                              "DirtyFields supports at most 64 fields"));
+    }
+
+    let vis = &input.vis;
+    let ident = &input.ident;
+    let tracker = format_ident!("Dirty{}", ident);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let all = all_bits(fields.len());
+
+    let names: Vec<String> = fields.iter()
+        .map(|f| f.ident.as_ref().unwrap().unraw().to_string())
+        .collect();
+    let accessors = fields.iter().enumerate().map(|(i, f)| {
+        let name = f.ident.as_ref().unwrap();
+        let ty = &f.ty;
+        let bit = 1u64 << i;
+        let set = format_ident!("set_{}", name);
+        let get_mut = format_ident!("{}_mut", name);
+        let dirty = format_ident!("{}_dirty", name);
+        let field = name.unraw();
+        let set_doc = format!("Set `{}`, sets its dirty flag.", field);
+        let mut_doc = format!("Writable `{}` return, sets its dirty flag.", field);
+        let dirty_doc = format!("Returns true if `{}` is dirty, false otherwise.", field);
+        quote! {
+            #[doc = #set_doc]
+            #vis fn #set<V: ::core::convert::Into<#ty>>(&mut self, val: V) {
+                self.value.#name = val.into();
+                self.fields |= #bit;
+            }
+
+            #[doc = #mut_doc]
+            #vis fn #get_mut(&mut self) -> &mut #ty {
+                self.fields |= #bit;
+                &mut self.value.#name
+            }
+
+            #[doc = #dirty_doc]
+            #vis fn #dirty(&self) -> bool {
+                self.fields & #bit != 0
+            }
+        }
+    });
+
+    let tracker_doc = format!("Tracks a dirty flag per field of `{}`.", ident);
+    Ok(quote! {
+        #[doc = #tracker_doc]
+        #vis struct #tracker #impl_generics #where_clause {
+            value: #ident #ty_generics,
+            fields: u64,
+        }
+
+        impl #impl_generics #tracker #ty_generics #where_clause {
+            /// Create a new tracker with every field dirty.
+            #vis fn new(val: #ident #ty_generics) -> Self {
+                #tracker {
+                    value: val,
+                    fields: #all,
+                }
+            }
+
+            /// Read the tracked struct.
+            #vis fn read(&self) -> &#ident #ty_generics {
+                &self.value
+            }
+
+            /// Consumes the tracker, returning the tracked struct.
+            #vis fn into_inner(self) -> #ident #ty_generics {
+                self.value
+            }
+
+            #(#accessors)*
+        }
+
+        impl #impl_generics ::dirty::DirtyFields for #tracker #ty_generics #where_clause {
+            type Inner = #ident #ty_generics;
+
+            const FIELDS: &'static [&'static str] = &[#(#names),*];
+
+            fn inner(&self) -> &Self::Inner {
+                &self.value
+            }
+
+            fn field_dirty(&self, index: usize) -> bool {
+                index < 64 && self.fields & (1u64 << index) != 0
+            }
+
+            fn clear_all(&mut self) {
+                self.fields = 0;
+            }
+        }
+
+        impl #impl_generics ::dirty::StructFields for #tracker #ty_generics #where_clause {}
+
+        impl #impl_generics ::core::ops::Deref for #tracker #ty_generics #where_clause {
+            type Target = #ident #ty_generics;
+            fn deref(&self) -> &Self::Target {
+                &self.value
+            }
+        }
+    })
+}
+
+fn expand_enum(input: &DeriveInput, data: &DataEnum) -> Result<proc_macro2::TokenStream, Error> {
+    let vis = &input.vis;
+    let ident = &input.ident;
+    let tracker = format_ident!("Dirty{}", ident);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // Bit 0 is the variant, followed by the fields of each variant in order.
+    let mut names = vec![String::from("variant")];
+    let mut accessors = Vec::new();
+    for variant in data.variants.iter() {
+        let variant_ident = &variant.ident;
+        let variant_name = variant_ident.unraw().to_string();
+        let prefix = snake_case(&variant_name);
+        for (i, f) in variant.fields.iter().enumerate() {
+            let (member, suffix) = match f.ident {
+                Some(ref name) => (Member::Named(name.clone()), name.unraw().to_string()),
+                None => (Member::Unnamed(Index::from(i)), i.to_string()),
+            };
+            names.push(format!("{}.{}", variant_name, suffix));
+            if names.len() > 64 {
+                return Err(Error::new(Span::call_site(),
+                                      "DirtyFields supports at most 63 enum fields"));
+            }
+            let ty = &f.ty;
+            let bit = 1u64 << (names.len() - 1);
+            let get_mut = format_ident!("{}_{}_mut", prefix, suffix);
+            let dirty = format_ident!("{}_{}_dirty", prefix, suffix);
+            let mut_doc = format!("Writable `{}.{}` return if the variant is `{}`, sets \
+                                   its dirty flag.", variant_name, suffix, variant_name);
+            let dirty_doc = format!("Returns true if `{}.{}` is dirty, false otherwise.",
+                                    variant_name, suffix);
+            accessors.push(quote! {
+                #[doc = #mut_doc]
+                #vis fn #get_mut(&mut self) -> ::core::option::Option<&mut #ty> {
+                    #[allow(unreachable_patterns)]
+                    match self.value {
+                        #ident::#variant_ident { #member: ref mut field, .. } => {
+                            self.fields |= #bit;
+                            ::core::option::Option::Some(field)
+                        },
+                        _ => ::core::option::Option::None,
+                    }
+                }
+
+                #[doc = #dirty_doc]
+                #vis fn #dirty(&self) -> bool {
+                    self.fields & #bit != 0
+                }
+            });
+        }
+    }
+    let all = all_bits(names.len());
+
+    let tracker_doc = format!("Tracks the variant and a dirty flag per field of `{}`.", ident);
+    Ok(quote! {
+        #[doc = #tracker_doc]
+        #vis struct #tracker #impl_generics #where_clause {
+            value: #ident #ty_generics,
+            fields: u64,
+        }
+
+        impl #impl_generics #tracker #ty_generics #where_clause {
+            /// Create a new tracker with every field dirty.
+            #vis fn new(val: #ident #ty_generics) -> Self {
+                #tracker {
+                    value: val,
+                    fields: #all,
+                }
+            }
+
+            /// Read the tracked enum.
+            #vis fn read(&self) -> &#ident #ty_generics {
+                &self.value
+            }
+
+            /// Set the value, marks the whole value dirty.
+            #vis fn set(&mut self, val: #ident #ty_generics) {
+                self.value = val;
+                self.fields = #all;
+            }
+
+            /// Writable value return, marks the whole value dirty.
+            #vis fn write(&mut self) -> &mut #ident #ty_generics {
+                self.fields = #all;
+                &mut self.value
+            }
+
+            /// Returns true if the variant may have changed, false otherwise.
+            #vis fn variant_dirty(&self) -> bool {
+                self.fields & 1 != 0
+            }
+
+            /// Consumes the tracker, returning the tracked enum.
+            #vis fn into_inner(self) -> #ident #ty_generics {
+                self.value
+            }
+
+            #(#accessors)*
+        }
+
+        impl #impl_generics ::dirty::DirtyFields for #tracker #ty_generics #where_clause {
+            type Inner = #ident #ty_generics;
+
+            const FIELDS: &'static [&'static str] = &[#(#names),*];
+
+            fn inner(&self) -> &Self::Inner {
+                &self.value
+            }
+
+            fn field_dirty(&self, index: usize) -> bool {
+                index < 64 && self.fields & (1u64 << index) != 0
+            }
+
+            fn clear_all(&mut self) {
+                self.fields = 0;
+            }
+        }
+
+        impl #impl_generics ::core::ops::Deref for #tracker #ty_generics #where_clause {
+            type Target = #ident #ty_generics;
+            fn deref(&self) -> &Self::Target {
+                &self.value
+            }
+        }
+    })
+}
+
+/// Returns a mask of the low n bits.
+fn all_bits(n: usize) -> proc_macro2::TokenStream {
+    if n == 64 {
+        quote!(!0u64)
+    } else {
+        let all = (1u64 << n) - 1;
+        quote!(#all)
+    }
+}
+
+/// Converts a CamelCase variant name to snake_case.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
@@ -0,0 +1,99 @@
+use dirty::DirtyFields;
+
+#[derive(DirtyFields)]
+pub struct User {
+    name: String,
+    email: String,
+    age: u32,
+}
+
+fn user() -> DirtyUser {
+    let mut user = DirtyUser::new(User {
+        name: String::from("a"),
+        email: String::from("a@example.com"),
+        age: 30,
+    });
+    user.clear_all();
+    user
+}
+
+#[test]
+fn new_all_dirty() {
+    let user = DirtyUser::new(User {
+        name: String::new(),
+        email: String::new(),
+        age: 0,
+    });
+    assert!(user.dirty());
+    assert!(user.dirty_fields() == vec!["name", "email", "age"]);
+}
+
+#[test]
+fn set_marks_only_field() {
+    let mut user = user();
+    assert!(!user.dirty());
+    user.set_name("x");
+    assert!(user.name_dirty());
+    assert!(!user.email_dirty());
+    assert!(user.dirty_fields() == vec!["name"]);
+    assert!(user.name == "x");
+}
+
+#[test]
+fn mut_marks_only_field() {
+    let mut user = user();
+    *user.age_mut() += 1;
+    assert!(user.dirty_fields() == vec!["age"]);
+    assert!(user.read().age == 31);
+}
+
+#[test]
+fn clear_all() {
+    let mut user = user();
+    user.set_email("b@example.com");
+    user.set_age(1u32);
+    assert!(user.dirty_fields() == vec!["email", "age"]);
+    user.clear_all();
+    assert!(!user.dirty());
+    assert!(user.dirty_fields().is_empty());
+}
+
+#[test]
+fn field_names() {
+    assert!(DirtyUser::FIELDS == ["name", "email", "age"]);
+}
+
+#[derive(DirtyFields)]
+pub enum Job {
+    Idle,
+    Running { pid: u32, progress: f32 },
+    Done(i32),
+}
+
+#[test]
+fn enum_field_paths() {
+    assert!(DirtyJob::FIELDS == ["variant", "Running.pid", "Running.progress", "Done.0"]);
+}
+
+#[test]
+fn enum_field_marks_only_field() {
+    let mut job = DirtyJob::new(Job::Running { pid: 7, progress: 0.0 });
+    job.clear_all();
+    *job.running_progress_mut().unwrap() = 0.5;
+    assert!(job.running_progress_dirty() && !job.running_pid_dirty());
+    assert!(!job.variant_dirty());
+    assert!(job.done_0_mut().is_none());
+    assert!(job.dirty_fields() == vec!["Running.progress"]);
+}
+
+#[test]
+fn enum_variant_change_marks_all() {
+    let mut job = DirtyJob::new(Job::Idle);
+    job.clear_all();
+    job.set(Job::Done(0));
+    assert!(job.variant_dirty() && job.done_0_dirty());
+    job.clear_all();
+    *job.write() = Job::Idle;
+    assert!(job.variant_dirty());
+    assert!(matches!(*job, Job::Idle));
+}
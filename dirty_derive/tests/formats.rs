@@ -0,0 +1,202 @@
+use dirty::{DirtyFields, JsonPatch, MergePatch, SqlUpdate, StructFields};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+#[derive(DirtyFields, Serialize)]
+pub struct User {
+    id: u64,
+    name: String,
+    age: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+}
+
+fn user(email: Option<&str>) -> User {
+    User {
+        id: 7,
+        name: String::from("ann"),
+        age: 30,
+        email: email.map(String::from),
+    }
+}
+
+fn clean(email: Option<&str>) -> DirtyUser {
+    let mut user = DirtyUser::new(user(email));
+    user.clear_all();
+    user
+}
+
+#[derive(DirtyFields, Serialize)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    bio: String,
+    #[serde(skip)]
+    views: u32,
+    score: i32,
+}
+
+#[test]
+fn merge_patch_nulls_missing_fields() {
+    let mut profile = DirtyProfile::new(Profile { bio: String::from("hi"), views: 3, score: 5 });
+    profile.clear_all();
+    profile.set_bio(String::new());
+    profile.set_views(0u32);
+    profile.set_score(0);
+    assert!(profile.to_merge_patch().unwrap() == json!({"bio": null, "views": null, "score": 0}));
+}
+
+#[test]
+fn merge_patch_of_dirty_fields() {
+    let mut dirty = clean(None);
+    assert!(dirty.to_merge_patch().unwrap() == json!({}));
+    dirty.set_age(30u32);
+    dirty.set_email(None::<String>);
+    assert!(dirty.to_merge_patch().unwrap() == json!({"age": 30, "email": null}));
+}
+
+/// Applies the add, replace and remove operations of an RFC 6902 patch on
+/// top level members, failing where the RFC requires the member to exist.
+fn apply(target: &mut Map<String, Value>, patch: &Value) -> Result<(), String> {
+    for op in patch.as_array().unwrap() {
+        let name = &op["path"].as_str().unwrap()[1..];
+        let exists = target.contains_key(name);
+        match (op["op"].as_str().unwrap(), exists) {
+            ("add", _) | ("replace", true) => {
+                target.insert(name.into(), op["value"].clone());
+            },
+            ("remove", true) => {
+                target.remove(name);
+            },
+            (op, _) => return Err(format!("{} of missing member {}", op, name)),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn json_patch_sets_dirty_fields() {
+    let mut dirty = clean(Some("ann@example.com"));
+    dirty.set_name("ann");
+    dirty.set_email(None::<String>);
+    assert!(dirty.to_json_patch().unwrap() == json!([
+        {"op": "add", "path": "/name", "value": "ann"},
+        {"op": "remove", "path": "/email"},
+    ]));
+}
+
+#[test]
+fn json_patch_applies_to_missing_members() {
+    let mut dirty = clean(None);
+    dirty.set_name("bob");
+    dirty.set_email(Some(String::from("bob@example.com")));
+    let mut target = Map::new();
+    target.insert("id".into(), json!(7));
+    apply(&mut target, &dirty.to_json_patch().unwrap()).unwrap();
+    assert!(Value::Object(target) == json!({"id": 7, "name": "bob", "email": "bob@example.com"}));
+    let mut existing = serde_json::to_value(user(Some("ann@example.com"))).unwrap();
+    apply(existing.as_object_mut().unwrap(), &dirty.to_json_patch().unwrap()).unwrap();
+    assert!(existing["name"] == "bob" && existing["email"] == "bob@example.com");
+}
+
+#[test]
+fn json_patch_from_old() {
+    let old = user(None);
+    let mut dirty = clean(None);
+    dirty.set_email(Some(String::from("ann@example.com")));
+    dirty.set_age(31u32);
+    dirty.set_name("ann");
+    assert!(dirty.to_json_patch_from(&old).unwrap() == json!([
+        {"op": "replace", "path": "/age", "value": 31},
+        {"op": "add", "path": "/email", "value": "ann@example.com"},
+    ]));
+    let mut back = clean(None);
+    back.set_email(None::<String>);
+    assert!(back.to_json_patch_from(dirty.read()).unwrap() == json!([{"op": "remove", "path": "/email"}]));
+}
+
+#[test]
+fn sql_update_of_dirty_columns() {
+    let mut dirty = clean(None);
+    assert!(dirty.sql_update(1).unwrap().is_none());
+    dirty.set_name("ann");
+    dirty.set_email(None::<String>);
+    let (set, params) = dirty.sql_update(1).unwrap().unwrap();
+    assert!(set == "name = $1, email = $2");
+    assert!(params == [json!("ann"), json!(null)]);
+    let (set, _) = dirty.sql_update(2).unwrap().unwrap();
+    assert!(set == "name = $2, email = $3");
+}
+
+#[test]
+fn field_mask_of_dirty_fields() {
+    let mut dirty = clean(None);
+    assert!(dirty.field_mask().paths.is_empty());
+    dirty.set_id(8u64);
+    dirty.set_age(31u32);
+    assert!(dirty.field_mask().paths == ["id", "age"]);
+}
+
+#[derive(DirtyFields, Serialize)]
+pub enum Shape {
+    Circle { radius: f32 },
+    Square(f32),
+}
+
+/// Implemented reports false unless the inherent impl, which requires the
+/// bound, shadows it.
+trait Implemented {
+    const IMPLEMENTED: bool = false;
+}
+
+impl<T> Implemented for T {}
+
+macro_rules! implements {
+    ($ty:ty: $bound:path) => {{
+        struct Check<T>(T);
+        #[allow(dead_code)]
+        impl<T> Check<T> where T: $bound + DirtyFields, T::Inner: Serialize {
+            const IMPLEMENTED: bool = true;
+        }
+        <Check<$ty>>::IMPLEMENTED
+    }};
+}
+
+#[test]
+fn enum_trackers_have_no_struct_formats() {
+    assert!(implements!(DirtyUser: StructFields));
+    assert!(implements!(DirtyUser: MergePatch));
+    assert!(!implements!(DirtyShape: StructFields));
+    assert!(!implements!(DirtyShape: MergePatch));
+    assert!(!implements!(DirtyShape: JsonPatch));
+    assert!(!implements!(DirtyShape: SqlUpdate));
+    let mut shape = DirtyShape::new(Shape::Circle { radius: 1.0 });
+    shape.clear_all();
+    *shape.circle_radius_mut().unwrap() = 2.0;
+    assert!(shape.dirty_fields() == vec!["Circle.radius"]);
+}
+
+#[derive(DirtyFields, Serialize)]
+pub struct Item {
+    r#type: String,
+    count: u32,
+}
+
+#[derive(DirtyFields)]
+pub enum Token {
+    Keyword { r#match: bool },
+}
+
+#[test]
+fn raw_identifiers_are_unraw() {
+    assert!(DirtyItem::FIELDS == ["type", "count"]);
+    let mut item = DirtyItem::new(Item { r#type: String::from("a"), count: 1 });
+    item.clear_all();
+    item.set_type("b");
+    assert!(item.type_dirty() && item.r#type == "b");
+    assert!(item.to_merge_patch().unwrap() == json!({"type": "b"}));
+    assert!(item.sql_update(1).unwrap().unwrap() == (String::from("type = $1"), vec![json!("b")]));
+    assert!(DirtyToken::FIELDS == ["variant", "Keyword.match"]);
+    let mut token = DirtyToken::new(Token::Keyword { r#match: false });
+    *token.keyword_match_mut().unwrap() = true;
+    assert!(token.keyword_match_dirty());
+}
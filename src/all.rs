@@ -0,0 +1,82 @@
+use track::Tracked;
+
+/// DirtyAll checks tuples and slices of references to tracked values together,
+/// e.g. `(&a, &b, &c).any_dirty()` to recompute when any input changed.
+pub trait DirtyAll {
+    /// Returns true if any value is dirty, false otherwise.
+    fn any_dirty(&self) -> bool;
+
+    /// Returns true if every value is dirty, false otherwise.
+    fn all_dirty(&self) -> bool;
+}
+
+/// ClearAll clears tuples and slices of mutable references to tracked values
+/// together, e.g. `(&mut a, &mut b, &mut c).clear_all()`.
+pub trait ClearAll: DirtyAll {
+    /// Clears every value.
+    fn clear_all(&mut self);
+}
+
+// The tuple impls, for arities 1 to 8, are generated with the Tracked tuple
+// impls by tuple_tracked! in track.rs.
+
+impl<T> DirtyAll for [&T] where T: Tracked + ?Sized {
+    fn any_dirty(&self) -> bool {
+        self.iter().any(|val| val.dirty())
+    }
+
+    fn all_dirty(&self) -> bool {
+        self.iter().all(|val| val.dirty())
+    }
+}
+
+impl<T> DirtyAll for [&mut T] where T: Tracked + ?Sized {
+    fn any_dirty(&self) -> bool {
+        self.iter().any(|val| val.dirty())
+    }
+
+    fn all_dirty(&self) -> bool {
+        self.iter().all(|val| val.dirty())
+    }
+}
+
+impl<T> ClearAll for [&mut T] where T: Tracked + ?Sized {
+    fn clear_all(&mut self) {
+        for val in self.iter_mut() {
+            val.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClearAll, DirtyAll};
+    use track::Tracked;
+    use {Dirty, DirtyCell};
+
+    #[test]
+    fn tuples_of_references() {
+        let mut a = Dirty::new_clean(1);
+        let mut b = Dirty::new_clean("b");
+        let mut c = DirtyCell::new_clean(3.0);
+        assert!(!(&a, &b, &c).any_dirty());
+        *b.write() = "c";
+        c.set(4.0);
+        assert!((&a, &b, &c).any_dirty() && !(&a, &b, &c).all_dirty());
+        (&mut a, &mut b, &mut c).clear_all();
+        assert!(!(&mut a, &mut b, &mut c).any_dirty());
+    }
+
+    #[test]
+    fn slices_of_references() {
+        let mut a = Dirty::new(1);
+        let mut b = Dirty::new_clean(2);
+        assert!([&a, &b][..].any_dirty());
+        {
+            let mut values: [&mut dyn Tracked; 2] = [&mut a, &mut b];
+            assert!(!values[..].all_dirty());
+            values.clear_all();
+        }
+        assert!(a.is_clean() && b.is_clean());
+    }
+}
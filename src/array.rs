@@ -0,0 +1,178 @@
+#[cfg(feature = "bytemuck")]
+use bytemuck::Pod;
+use core::array;
+use core::fmt;
+use core::ops::{Deref, Index, IndexMut};
+
+use bits::{DirtyBits, SetIndices};
+#[cfg(feature = "bytemuck")]
+use pod::DirtyBytes;
+
+/// DirtyArray wraps a fixed array of N values of type T with a dirty bit per
+/// index stored inline in a DirtyBits of WORDS 64 bit words, without
+/// allocating.
+/// WORDS defaults to 1 for up to 64 values and must cover N, which is checked
+/// at compile time, e.g. `DirtyArray<T, 100, 2>`.
+/// Use deref (`dirty_array[i]`) to read values, a mutable index marks the
+/// value dirty.
+pub struct DirtyArray<T, const N: usize, const WORDS: usize = 1> {
+    values: [T; N],
+    bits: DirtyBits<N, WORDS>,
+}
+
+impl<T, const N: usize, const WORDS: usize> DirtyArray<T, N, WORDS> {
+    /// Create a new DirtyArray with every value dirty.
+    pub fn new(values: [T; N]) -> DirtyArray<T, N, WORDS> {
+        DirtyArray {
+            values,
+            bits: DirtyBits::new(),
+        }
+    }
+
+    /// Create a new DirtyArray with every value clean.
+    pub fn new_clean(values: [T; N]) -> DirtyArray<T, N, WORDS> {
+        DirtyArray {
+            values,
+            bits: DirtyBits::new_clean(),
+        }
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns true if there are no values, false otherwise.
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Returns true if the value at index is dirty, false otherwise.
+    pub fn dirty(&self, index: usize) -> bool {
+        index < N && self.bits.dirty(index)
+    }
+
+    /// Returns true if any value is dirty, false otherwise.
+    pub fn any_dirty(&self) -> bool {
+        self.bits.any()
+    }
+
+    /// Writable value return, sets the value's dirty flag.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= N {
+            return None;
+        }
+        self.bits.mark(index);
+        self.values.get_mut(index)
+    }
+
+    /// Sets the value at index, marking it dirty.
+    pub fn set(&mut self, index: usize, val: T) {
+        self.values[index] = val;
+        self.bits.mark(index);
+    }
+
+    /// Clears the dirty flag of the value at index.
+    pub fn clear(&mut self, index: usize) {
+        if index < N {
+            self.bits.clear(index);
+        }
+    }
+
+    /// Clears the dirty flag of every value.
+    pub fn clear_all(&mut self) {
+        self.bits.clear_all();
+    }
+
+    /// Iterate over the indices of the dirty values in increasing order.
+    pub fn dirty_indices(&self) -> SetIndices<'_> {
+        self.bits.iter_set_indices()
+    }
+
+    /// Iterate over the dirty values and their indices.
+    pub fn iter_dirty(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.dirty_indices().map(move |i| (i, &self.values[i]))
+    }
+
+    /// Consumes the DirtyArray, returning the inner array.
+    pub fn into_inner(self) -> [T; N] {
+        self.values
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<T, const N: usize, const WORDS: usize> DirtyArray<T, N, WORDS> where T: Pod {
+    /// Iterate over the byte offsets and bytes of each run of consecutive
+    /// dirty elements.
+    pub fn dirty_bytes(&self) -> impl Iterator<Item = (usize, &[u8])> {
+        DirtyBytes::new(&self.values, self.dirty_indices())
+    }
+}
+
+impl<T, const N: usize, const WORDS: usize> Deref for DirtyArray<T, N, WORDS> {
+    type Target = [T; N];
+    fn deref(&self) -> &[T; N] {
+        &self.values
+    }
+}
+
+impl<T, const N: usize, const WORDS: usize> Index<usize> for DirtyArray<T, N, WORDS> {
+    type Output = T;
+    fn index(&self, index: usize) -> &T {
+        &self.values[index]
+    }
+}
+
+/// Like get_mut() this sets the value's dirty flag.
+impl<T, const N: usize, const WORDS: usize> IndexMut<usize> for DirtyArray<T, N, WORDS> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let val = &mut self.values[index];
+        self.bits.mark(index);
+        val
+    }
+}
+
+impl<T, const N: usize, const WORDS: usize> Default for DirtyArray<T, N, WORDS> where T: Default {
+    fn default() -> Self {
+        DirtyArray::new(array::from_fn(|_| T::default()))
+    }
+}
+
+impl<T, const N: usize, const WORDS: usize> fmt::Debug for DirtyArray<T, N, WORDS> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DirtyArray")
+            .field("values", &self.values)
+            .field("bits", &self.bits)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtyArray;
+    use std::vec::Vec;
+
+    #[test]
+    fn per_index_flags() {
+        let mut table: DirtyArray<f32, 16> = DirtyArray::new_clean([0.0; 16]);
+        assert!(!table.any_dirty());
+        *table.get_mut(3).unwrap() = 1.0;
+        table[15] = 2.0;
+        assert!(table.get_mut(16).is_none());
+        let dirty: Vec<(usize, f32)> = table.iter_dirty().map(|(i, &v)| (i, v)).collect();
+        assert!(dirty == [(3, 1.0), (15, 2.0)]);
+        table.clear(3);
+        assert!(!table.dirty(3) && table.dirty(15));
+        table.clear_all();
+        assert!(table.dirty_indices().next().is_none());
+    }
+
+    #[test]
+    fn multiple_words() {
+        let table: DirtyArray<u8, 100, 2> = DirtyArray::new([0; 100]);
+        assert!(table.dirty_indices().count() == 100);
+        assert!(!table.dirty(100));
+        let empty: DirtyArray<u8, 0> = DirtyArray::default();
+        assert!(empty.is_empty() && !empty.any_dirty());
+    }
+}
@@ -0,0 +1,297 @@
+use std::future::Future;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::task::{Context, Poll, Waker};
+use std::vec::Vec;
+
+#[cfg(feature = "futures")]
+use futures_core::Stream;
+
+/// AsyncDirty wraps a value of type T like AtomicDirty and additionally wakes
+/// tasks waiting in wait_dirty() when the value becomes dirty, so a flush
+/// task may sleep instead of polling the flag.
+pub struct AsyncDirty<T> {
+    value: T,
+    dirty: AtomicBool,
+    transitions: AtomicUsize,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl<T> AsyncDirty<T> {
+    /// Create a new AsyncDirty.
+    pub fn new(val: T) -> AsyncDirty<T> {
+        AsyncDirty {
+            value: val,
+            dirty: AtomicBool::new(true),
+            transitions: AtomicUsize::new(0),
+            wakers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Create a new clean AsyncDirty.
+    pub fn new_clean(val: T) -> AsyncDirty<T> {
+        AsyncDirty {
+            value: val,
+            dirty: AtomicBool::new(false),
+            transitions: AtomicUsize::new(0),
+            wakers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self, order: Ordering) -> bool {
+        self.dirty.load(order)
+    }
+
+    /// Sets the dirty flag with AcqRel ordering, waking waiting tasks.
+    pub fn mark(&self) {
+        if !self.dirty.swap(true, Ordering::AcqRel) {
+            self.transitions.fetch_add(1, Ordering::Release);
+        }
+        self.wake();
+    }
+
+    /// Clears the dirty flag with Release ordering.
+    pub fn clear(&self) {
+        self.dirty.store(false, Ordering::Release);
+    }
+
+    /// Writable value return, sets the dirty flag waking waiting tasks.
+    pub fn write(&mut self) -> &mut T {
+        if !*self.dirty.get_mut() {
+            *self.dirty.get_mut() = true;
+            *self.transitions.get_mut() += 1;
+        }
+        // Unique access means no other thread holds the lock, skip locking.
+        for waker in self.wakers.get_mut().unwrap().drain(..) {
+            waker.wake();
+        }
+        &mut self.value
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Read the value only if modified since last read.
+    pub fn read_dirty(&self, order: Ordering) -> Option<&T> {
+        match self.dirty(order) {
+            true => Some(&self.value),
+            false => None,
+        }
+    }
+
+    /// Returns a future resolving once the value is dirty, immediately if it
+    /// already is.
+    pub fn wait_dirty(&self) -> WaitDirty<'_, T> {
+        WaitDirty {
+            dirty: self,
+        }
+    }
+
+    /// Returns a stream yielding an item each time the value becomes dirty
+    /// after the stream was created, transitions between polls are coalesced
+    /// into a single item.
+    #[cfg(feature = "futures")]
+    pub fn changes(&self) -> ChangeStream<'_, T> {
+        ChangeStream {
+            dirty: self,
+            seen: self.transitions.load(Ordering::Acquire),
+        }
+    }
+
+    /// Consumes the AsyncDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    fn wake(&self) {
+        let wakers: Vec<Waker> = self.wakers.lock().unwrap().drain(..).collect();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Deref for AsyncDirty<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Default for AsyncDirty<T> where T: Default {
+    fn default() -> Self {
+        AsyncDirty::new(T::default())
+    }
+}
+
+/// Future returned by AsyncDirty::wait_dirty().
+pub struct WaitDirty<'a, T: 'a> {
+    dirty: &'a AsyncDirty<T>,
+}
+
+impl<'a, T> Future for WaitDirty<'a, T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.dirty.dirty(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+        {
+            let mut wakers = self.dirty.wakers.lock().unwrap();
+            if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+        }
+        // Check again as mark() may have run before the waker was registered.
+        match self.dirty.dirty(Ordering::Acquire) {
+            true => Poll::Ready(()),
+            false => Poll::Pending,
+        }
+    }
+}
+
+/// Stream returned by AsyncDirty::changes().
+#[cfg(feature = "futures")]
+pub struct ChangeStream<'a, T: 'a> {
+    dirty: &'a AsyncDirty<T>,
+    seen: usize,
+}
+
+#[cfg(feature = "futures")]
+impl<'a, T> ChangeStream<'a, T> {
+    fn poll_seen(&mut self) -> Poll<Option<()>> {
+        let transitions = self.dirty.transitions.load(Ordering::Acquire);
+        match transitions != self.seen {
+            true => {
+                self.seen = transitions;
+                Poll::Ready(Some(()))
+            }
+            false => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<'a, T> Stream for ChangeStream<'a, T> {
+    type Item = ();
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<()>> {
+        let this = self.get_mut();
+        if this.poll_seen().is_ready() {
+            return Poll::Ready(Some(()));
+        }
+        {
+            let mut wakers = this.dirty.wakers.lock().unwrap();
+            if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+        }
+        // Check again as mark() may have run before the waker was registered.
+        this.poll_seen()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncDirty;
+    use std::boxed::Box;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::sync::atomic::Ordering;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+    use std::time::Duration;
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = Box::pin(fut);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(out) = fut.as_mut().poll(&mut cx) {
+                return out;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn wait_dirty_ready_when_dirty() {
+        let dirty = AsyncDirty::new(0);
+        block_on(dirty.wait_dirty());
+        assert!(dirty.dirty(Ordering::Acquire));
+    }
+
+    #[test]
+    fn wait_dirty_pending_when_clean() {
+        let dirty = AsyncDirty::new(0);
+        dirty.clear();
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = dirty.wait_dirty();
+        assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+        dirty.mark();
+        assert!(Pin::new(&mut fut).poll(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn new_clean() {
+        let dirty = AsyncDirty::new_clean(0);
+        assert!(!dirty.dirty(Ordering::Acquire));
+    }
+
+    #[test]
+    fn wait_dirty_woken_by_other_thread() {
+        let dirty = Arc::new(AsyncDirty::new(0));
+        dirty.clear();
+        let producer = {
+            let dirty = dirty.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                dirty.mark();
+            })
+        };
+        block_on(dirty.wait_dirty());
+        assert!(dirty.dirty(Ordering::Acquire));
+        producer.join().unwrap();
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn changes_yield_on_transitions() {
+        use futures_core::Stream;
+
+        let mut dirty = AsyncDirty::new(0);
+        *dirty.write() += 1;
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        {
+            let mut changes = dirty.changes();
+            assert!(Pin::new(&mut changes).poll_next(&mut cx).is_pending());
+            dirty.mark();
+            assert!(Pin::new(&mut changes).poll_next(&mut cx).is_pending());
+            dirty.clear();
+            dirty.mark();
+            dirty.clear();
+            dirty.mark();
+            assert!(Pin::new(&mut changes).poll_next(&mut cx) == Poll::Ready(Some(())));
+            assert!(Pin::new(&mut changes).poll_next(&mut cx).is_pending());
+        }
+        dirty.clear();
+        *dirty.write() += 1;
+        assert!(*dirty == 2);
+    }
+}
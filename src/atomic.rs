@@ -0,0 +1,166 @@
+use core::fmt;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use flag::SharedFlag;
+use Dirty;
+
+/// AtomicDirty wraps a value of type T like Dirty but keeps the dirty flag in
+/// an AtomicBool, so the flag may be checked, set and cleared through a shared
+/// reference from any thread.
+/// Writing the value itself still requires unique access with write().
+/// This is a thin wrapper over Dirty<T, AtomicBool> taking explicit orderings.
+pub struct AtomicDirty<T> {
+    inner: Dirty<T, AtomicBool>,
+}
+
+impl<T> AtomicDirty<T> {
+    /// Create a new AtomicDirty.
+    pub const fn new(val: T) -> AtomicDirty<T> {
+        AtomicDirty {
+            inner: Dirty::from_flag(val, AtomicBool::new(true)),
+        }
+    }
+
+    /// Create a new clean AtomicDirty.
+    pub const fn new_clean(val: T) -> AtomicDirty<T> {
+        AtomicDirty {
+            inner: Dirty::from_flag(val, AtomicBool::new(false)),
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self, order: Ordering) -> bool {
+        self.inner.flag.load(order)
+    }
+
+    /// Sets the dirty flag with Release ordering.
+    pub fn mark(&self) {
+        self.inner.flag.mark_shared();
+    }
+
+    /// Clears the dirty flag with Release ordering.
+    pub fn clear(&self) {
+        self.inner.flag.store(false, Ordering::Release);
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.inner.write()
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        self.inner.read()
+    }
+
+    /// Read the value only if modified since last read.
+    pub fn read_dirty(&self, order: Ordering) -> Option<&T> {
+        match self.dirty(order) {
+            true => Some(self.inner.read()),
+            false => None,
+        }
+    }
+
+    /// Consumes the AtomicDirty, returning the Dirty it wraps.
+    pub fn into_dirty(self) -> Dirty<T, AtomicBool> {
+        self.inner
+    }
+
+    /// Consumes the AtomicDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T> From<Dirty<T, AtomicBool>> for AtomicDirty<T> {
+    fn from(inner: Dirty<T, AtomicBool>) -> AtomicDirty<T> {
+        AtomicDirty { inner }
+    }
+}
+
+impl<T> Deref for AtomicDirty<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.inner.read()
+    }
+}
+
+impl<T> Default for AtomicDirty<T> where T: Default {
+    fn default() -> Self {
+        AtomicDirty::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for AtomicDirty<T> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AtomicDirty")
+            .field("value", self.inner.read())
+            .field("dirty", &self.dirty(Ordering::Acquire))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AtomicDirty;
+    use std::sync::Arc;
+    use std::sync::atomic::Ordering;
+    use std::thread;
+
+    #[test]
+    fn new_clean() {
+        let dirty = AtomicDirty::new_clean(0);
+        assert!(!dirty.dirty(Ordering::Acquire));
+    }
+
+    #[test]
+    fn new_dirty() {
+        let dirty = AtomicDirty::new(0);
+        assert!(dirty.dirty(Ordering::Acquire));
+    }
+
+    #[test]
+    fn mark_and_clear_shared() {
+        let dirty = AtomicDirty::new(0);
+        dirty.clear();
+        assert!(!dirty.dirty(Ordering::Acquire));
+        assert!(dirty.read_dirty(Ordering::Acquire).is_none());
+        dirty.mark();
+        assert!(dirty.dirty(Ordering::Acquire));
+        assert!(dirty.read_dirty(Ordering::Acquire) == Some(&0));
+    }
+
+    #[test]
+    fn write_sets_flag() {
+        let mut dirty = AtomicDirty::new(0);
+        dirty.clear();
+        *dirty.write() += 1;
+        assert!(dirty.dirty(Ordering::Acquire));
+        assert!(*dirty == 1);
+    }
+
+    #[test]
+    fn clear_across_threads() {
+        let dirty = Arc::new(AtomicDirty::new(1));
+        let consumer = {
+            let dirty = dirty.clone();
+            thread::spawn(move || {
+                let val = *dirty.read_dirty(Ordering::Acquire).unwrap();
+                dirty.clear();
+                val
+            })
+        };
+        assert!(consumer.join().unwrap() == 1);
+        assert!(!dirty.dirty(Ordering::Acquire));
+    }
+
+    #[test]
+    fn into_dirty_keeps_flag() {
+        let dirty = AtomicDirty::new_clean(1);
+        dirty.mark();
+        let inner = dirty.into_dirty();
+        assert!(inner.dirty() && *inner == 1);
+        assert!(AtomicDirty::from(inner).dirty(Ordering::Acquire));
+    }
+}
@@ -0,0 +1,140 @@
+use core::fmt;
+use core::ops::Deref;
+
+/// BaselineDirty wraps a value of type T like Dirty and keeps a pinned
+/// baseline copy of the value, such as the last saved value, so consumers may
+/// ask whether the value actually differs from it whatever the dirty flag says.
+/// The baseline is only changed by set_baseline(), not by clear().
+pub struct BaselineDirty<T> where T: Clone + PartialEq {
+    value: T,
+    baseline: T,
+    dirty: bool,
+}
+
+impl<T> BaselineDirty<T> where T: Clone + PartialEq {
+    /// Create a new BaselineDirty with val as the baseline.
+    pub fn new(val: T) -> BaselineDirty<T> {
+        BaselineDirty {
+            baseline: val.clone(),
+            value: val,
+            dirty: true,
+        }
+    }
+
+    /// Create a new clean BaselineDirty with val as the baseline.
+    pub fn new_clean(val: T) -> BaselineDirty<T> {
+        BaselineDirty {
+            baseline: val.clone(),
+            value: val,
+            dirty: false,
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.value
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.dirty = true;
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Read the baseline.
+    pub fn baseline(&self) -> &T {
+        &self.baseline
+    }
+
+    /// Pins a copy of the value as the baseline.
+    pub fn set_baseline(&mut self) {
+        self.baseline.clone_from(&self.value);
+    }
+
+    /// Returns true if the value differs from the baseline, false otherwise.
+    pub fn changed_from_baseline(&self) -> bool {
+        self.value != self.baseline
+    }
+
+    /// Restores the value to the baseline, setting the dirty flag if it
+    /// differed.
+    pub fn revert_to_baseline(&mut self) {
+        if self.changed_from_baseline() {
+            self.value.clone_from(&self.baseline);
+            self.dirty = true;
+        }
+    }
+
+    /// Clears the dirty flag.
+    pub fn clear(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Consumes the BaselineDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for BaselineDirty<T> where T: Clone + PartialEq {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Default for BaselineDirty<T> where T: Clone + PartialEq + Default {
+    fn default() -> Self {
+        BaselineDirty::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for BaselineDirty<T> where T: Clone + PartialEq + fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BaselineDirty")
+            .field("value", &self.value)
+            .field("baseline", &self.baseline)
+            .field("dirty", &self.dirty)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BaselineDirty;
+
+    #[test]
+    fn undone_writes_match_baseline() {
+        let mut form = BaselineDirty::new_clean(1);
+        form.set(2);
+        assert!(form.dirty() && form.changed_from_baseline());
+        form.set(1);
+        assert!(form.dirty() && !form.changed_from_baseline());
+        form.clear();
+        form.set(3);
+        form.set_baseline();
+        assert!(!form.changed_from_baseline() && *form.baseline() == 3);
+    }
+
+    #[test]
+    fn revert_to_baseline() {
+        let mut form = BaselineDirty::new_clean(1);
+        form.revert_to_baseline();
+        assert!(!form.dirty());
+        *form.write() += 4;
+        form.clear();
+        form.revert_to_baseline();
+        assert!(form.dirty() && *form == 1);
+    }
+}
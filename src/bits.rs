@@ -0,0 +1,248 @@
+/// DirtyBits is a fixed size set of N dirty bits stored in WORDS 64 bit
+/// words, tracking N independent entries.
+/// WORDS defaults to 1 for up to 64 bits and must cover N, which is checked at
+/// compile time, e.g. DirtyBits<100, 2>.
+/// A new DirtyBits starts with every bit dirty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DirtyBits<const N: usize, const WORDS: usize = 1> {
+    words: [u64; WORDS],
+}
+
+impl<const N: usize, const WORDS: usize> DirtyBits<N, WORDS> {
+    /// Number of bits tracked.
+    pub const LEN: usize = N;
+
+    const COVERED: () = assert!(N <= WORDS * 64, "DirtyBits needs WORDS * 64 to be at least N");
+
+    // Every one of the N bits set, the unused high bits of the last words
+    // left clear.
+    const ALL: [u64; WORDS] = {
+        let mut words = [0; WORDS];
+        let mut i = 0;
+        while i < WORDS {
+            let start = i * 64;
+            words[i] = match (N >= start + 64, N > start) {
+                (true, _) => !0,
+                (false, true) => (1 << (N - start)) - 1,
+                (false, false) => 0,
+            };
+            i += 1;
+        }
+        words
+    };
+
+    /// Create a new DirtyBits with every bit dirty.
+    pub const fn new() -> DirtyBits<N, WORDS> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::COVERED;
+        DirtyBits {
+            words: Self::ALL,
+        }
+    }
+
+    /// Create a new DirtyBits with every bit clean.
+    pub const fn new_clean() -> DirtyBits<N, WORDS> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::COVERED;
+        DirtyBits {
+            words: [0; WORDS],
+        }
+    }
+
+    /// Returns the number of bits tracked.
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns true if no bits are tracked, false otherwise.
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Returns true if bit i is dirty, false otherwise.
+    /// Panics if i is not less than N.
+    pub fn dirty(&self, i: usize) -> bool {
+        Self::check(i);
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// Returns true if any bit is dirty, false otherwise.
+    pub fn any(&self) -> bool {
+        self.words.iter().any(|&w| w != 0)
+    }
+
+    /// Marks bit i dirty.
+    /// Panics if i is not less than N.
+    pub fn mark(&mut self, i: usize) {
+        Self::check(i);
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    /// Marks every bit dirty.
+    pub fn mark_all(&mut self) {
+        self.words = Self::ALL;
+    }
+
+    /// Clears bit i.
+    /// Panics if i is not less than N.
+    pub fn clear(&mut self, i: usize) {
+        Self::check(i);
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+
+    /// Clears every bit.
+    pub fn clear_all(&mut self) {
+        self.words = [0; WORDS];
+    }
+
+    /// Returns the words storing the bits, bit i is bit i % 64 of word i / 64.
+    /// Bits from N on are always clear.
+    pub fn as_words(&self) -> &[u64; WORDS] {
+        &self.words
+    }
+
+    /// Iterate over the indices of the dirty bits in increasing order.
+    pub fn iter_set_indices(&self) -> SetIndices<'_> {
+        SetIndices::new(&self.words)
+    }
+
+    /// Returns the index of the first dirty bit at or after from.
+    pub fn find_next_dirty(&self, from: usize) -> Option<usize> {
+        find_next(&self.words, from)
+    }
+
+    /// Returns the number of dirty bits.
+    pub fn count_dirty(&self) -> usize {
+        count(&self.words)
+    }
+
+    fn check(i: usize) {
+        assert!(i < N, "bit index {} out of range for DirtyBits of {} bits", i, N);
+    }
+}
+
+impl<const N: usize, const WORDS: usize> Default for DirtyBits<N, WORDS> {
+    fn default() -> Self {
+        DirtyBits::new()
+    }
+}
+
+/// Returns the index of the first set bit at or after from.
+pub(crate) fn find_next(words: &[u64], from: usize) -> Option<usize> {
+    let mut index = from / 64;
+    // Mask off the bits before from in the first word.
+    let mut word = *words.get(index)? & (!0 << (from % 64));
+    while word == 0 {
+        index += 1;
+        word = *words.get(index)?;
+    }
+    Some(index * 64 + word.trailing_zeros() as usize)
+}
+
+/// Returns the number of set bits.
+pub(crate) fn count(words: &[u64]) -> usize {
+    words.iter().map(|w| w.count_ones() as usize).sum()
+}
+
+/// Iterator over the indices of the dirty bits of a DirtyBits or the dirty
+/// pages of a PagedDirty.
+pub struct SetIndices<'a> {
+    words: &'a [u64],
+    index: usize,
+    word: u64,
+}
+
+impl<'a> SetIndices<'a> {
+    pub(crate) fn new(words: &'a [u64]) -> SetIndices<'a> {
+        SetIndices {
+            words,
+            index: 0,
+            word: words.first().cloned().unwrap_or(0),
+        }
+    }
+}
+
+impl<'a> Iterator for SetIndices<'a> {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        while self.word == 0 {
+            self.index += 1;
+            self.word = *self.words.get(self.index)?;
+        }
+        let bit = self.word.trailing_zeros() as usize;
+        // Clear the lowest set bit.
+        self.word &= self.word - 1;
+        Some(self.index * 64 + bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtyBits;
+    use std::vec::Vec;
+
+    #[test]
+    fn new_all_dirty() {
+        let bits = DirtyBits::<128, 2>::new();
+        assert!(bits.len() == 128);
+        assert!(bits.dirty(127));
+        assert!(bits.iter_set_indices().count() == 128);
+        assert!(!DirtyBits::<128, 2>::new_clean().any());
+    }
+
+    #[test]
+    fn exactly_n_bits() {
+        let mut bits = DirtyBits::<70, 2>::new();
+        assert!(bits.len() == 70 && bits.count_dirty() == 70);
+        assert!(bits.iter_set_indices().last() == Some(69));
+        bits.clear_all();
+        bits.mark_all();
+        assert!(bits.as_words() == &[!0, 0x3f]);
+        assert!(DirtyBits::<10>::new().count_dirty() == 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn index_past_n_panics() {
+        let mut bits = DirtyBits::<70, 2>::new_clean();
+        bits.mark(70);
+    }
+
+    #[test]
+    fn mark_and_clear() {
+        let mut bits = DirtyBits::<128, 2>::new_clean();
+        bits.mark(3);
+        bits.mark(64);
+        bits.mark(127);
+        assert!(bits.any());
+        assert!(bits.iter_set_indices().collect::<Vec<usize>>() == [3, 64, 127]);
+        bits.clear(64);
+        assert!(!bits.dirty(64));
+        assert!(bits.iter_set_indices().collect::<Vec<usize>>() == [3, 127]);
+        bits.clear_all();
+        assert!(bits.iter_set_indices().next().is_none());
+    }
+
+    #[test]
+    fn find_and_count() {
+        let mut bits = DirtyBits::<192, 3>::new_clean();
+        assert!(bits.find_next_dirty(0).is_none());
+        bits.mark(5);
+        bits.mark(130);
+        assert!(bits.find_next_dirty(0) == Some(5));
+        assert!(bits.find_next_dirty(5) == Some(5));
+        assert!(bits.find_next_dirty(6) == Some(130));
+        assert!(bits.find_next_dirty(131).is_none());
+        assert!(bits.find_next_dirty(500).is_none());
+        assert!(bits.count_dirty() == 2);
+        assert!(DirtyBits::<192, 3>::new().count_dirty() == 192);
+    }
+
+    #[test]
+    fn empty_set() {
+        let bits = DirtyBits::<0, 0>::new();
+        assert!(bits.is_empty());
+        assert!(bits.iter_set_indices().next().is_none());
+        assert!(DirtyBits::<0>::new().is_empty() && !DirtyBits::<0>::new().any());
+    }
+}
@@ -0,0 +1,186 @@
+use alloc::boxed::Box;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+/// Low bit of the pointer, set when dirty.
+const DIRTY: usize = 1;
+
+/// DirtyBox is a Box of a value of type T with the dirty flag stored in the
+/// low bit of the pointer, so it stays pointer sized in large collections.
+/// T must be aligned to at least 2 bytes, which is checked at compile time.
+pub struct DirtyBox<T> {
+    ptr: NonNull<T>,
+    owned: PhantomData<Box<T>>,
+}
+
+// SAFETY: DirtyBox owns its value like Box.
+unsafe impl<T> Send for DirtyBox<T> where T: Send {}
+// SAFETY: DirtyBox only gives shared access to its value through &self.
+unsafe impl<T> Sync for DirtyBox<T> where T: Sync {}
+
+impl<T> DirtyBox<T> {
+    const ALIGNED: () = assert!(mem::align_of::<T>() >= 2, "DirtyBox needs an alignment of 2 or more");
+
+    /// Create a new DirtyBox.
+    pub fn new(val: T) -> DirtyBox<T> {
+        DirtyBox::from_box(Box::new(val), true)
+    }
+
+    /// Create a new clean DirtyBox.
+    pub fn new_clean(val: T) -> DirtyBox<T> {
+        DirtyBox::from_box(Box::new(val), false)
+    }
+
+    /// Create a new DirtyBox from a Box and dirty flag.
+    pub fn from_box(boxed: Box<T>, dirty: bool) -> DirtyBox<T> {
+        let () = Self::ALIGNED;
+        let ptr = NonNull::from(Box::leak(boxed));
+        DirtyBox {
+            ptr: ptr.map_addr(|addr| addr | dirty as usize),
+            owned: PhantomData,
+        }
+    }
+
+    fn value(&self) -> NonNull<T> {
+        let ptr = self.ptr.as_ptr().map_addr(|addr| addr & !DIRTY);
+        // SAFETY: the value is aligned to 2 or more, so its address is non
+        // zero with the low bit cleared.
+        unsafe { NonNull::new_unchecked(ptr) }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.ptr.addr().get() & DIRTY != 0
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.ptr = self.ptr.map_addr(|addr| addr | DIRTY);
+        // SAFETY: the pointer came from a Box owned by self, borrowed mutably.
+        unsafe { &mut *self.value().as_ptr() }
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        *self.write() = val;
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        // SAFETY: the pointer came from a Box owned by self.
+        unsafe { self.value().as_ref() }
+    }
+
+    /// Clears the dirty flag.
+    pub fn clear(&mut self) {
+        self.ptr = self.value();
+    }
+
+    /// Read the value only if modified since last read, clearing the dirty
+    /// flag.
+    pub fn take_dirty(&mut self) -> Option<&T> {
+        match self.dirty() {
+            true => {
+                self.clear();
+                Some(self.read())
+            },
+            false => None,
+        }
+    }
+
+    /// Consumes the DirtyBox, returning the Box and the dirty flag.
+    pub fn into_parts(self) -> (Box<T>, bool) {
+        let dirty = self.dirty();
+        let ptr = self.value();
+        mem::forget(self);
+        // SAFETY: the pointer came from a Box, ownership moves back to it as
+        // self was forgotten.
+        (unsafe { Box::from_raw(ptr.as_ptr()) }, dirty)
+    }
+
+    /// Consumes the DirtyBox, returning the inner value.
+    pub fn into_inner(self) -> T {
+        *self.into_parts().0
+    }
+}
+
+impl<T> Drop for DirtyBox<T> {
+    fn drop(&mut self) {
+        // SAFETY: the pointer came from a Box owned by self.
+        drop(unsafe { Box::from_raw(self.value().as_ptr()) });
+    }
+}
+
+impl<T> Deref for DirtyBox<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.read()
+    }
+}
+
+impl<T> DerefMut for DirtyBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.write()
+    }
+}
+
+impl<T> Clone for DirtyBox<T> where T: Clone {
+    fn clone(&self) -> Self {
+        DirtyBox::from_box(Box::new(self.read().clone()), self.dirty())
+    }
+}
+
+impl<T> Default for DirtyBox<T> where T: Default {
+    fn default() -> Self {
+        DirtyBox::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for DirtyBox<T> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DirtyBox")
+            .field("value", self.read())
+            .field("dirty", &self.dirty())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtyBox;
+    use core::mem;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    #[test]
+    fn pointer_sized() {
+        assert!(mem::size_of::<DirtyBox<u64>>() == mem::size_of::<usize>());
+        assert!(mem::size_of::<Option<DirtyBox<u64>>>() == mem::size_of::<usize>());
+    }
+
+    #[test]
+    fn flag_in_pointer() {
+        let mut boxed = DirtyBox::new_clean(Vec::from([1u16, 2]));
+        assert!(!boxed.dirty());
+        boxed.write().push(3);
+        assert!(boxed.dirty() && boxed.len() == 3);
+        assert!(boxed.take_dirty().map(|v| v.len()) == Some(3));
+        assert!(boxed.take_dirty().is_none());
+        boxed.set(Vec::new());
+        let (inner, dirty) = boxed.clone().into_parts();
+        assert!(dirty && inner.is_empty());
+        assert!(boxed.into_inner().is_empty());
+    }
+
+    #[test]
+    fn drops_value() {
+        let shared = Rc::new(0u32);
+        let boxed = DirtyBox::new(shared.clone());
+        assert!(Rc::strong_count(&shared) == 2);
+        drop(boxed);
+        assert!(Rc::strong_count(&shared) == 1);
+    }
+}
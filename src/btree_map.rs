@@ -0,0 +1,176 @@
+use alloc::collections::BTreeMap;
+use alloc::collections::btree_map::Entry;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::mem;
+use core::ops::Deref;
+
+use change::{KeyChange, State};
+
+/// DirtyBTreeMap wraps a BTreeMap<K, V> recording which keys were inserted,
+/// updated or removed since the last clear(), yielding the changes in key
+/// order.
+/// Use deref (dirty_map.get(k)) to read the inner map.
+pub struct DirtyBTreeMap<K, V> {
+    map: BTreeMap<K, V>,
+    changes: BTreeMap<K, State>,
+}
+
+impl<K, V> DirtyBTreeMap<K, V> where K: Ord + Clone {
+    /// Create a new empty DirtyBTreeMap.
+    pub fn new() -> DirtyBTreeMap<K, V> {
+        DirtyBTreeMap {
+            map: BTreeMap::new(),
+            changes: BTreeMap::new(),
+        }
+    }
+
+    /// Returns true if any key changed since the last clear(), false otherwise.
+    pub fn dirty(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// Inserts a value, recording the key as inserted or updated.
+    pub fn insert(&mut self, key: K, val: V) -> Option<V> {
+        let old = self.map.insert(key.clone(), val);
+        let state = State::after_insert(old.is_some(), self.changes.get(&key).cloned());
+        self.changes.insert(key, state);
+        old
+    }
+
+    /// Writable value return, records the key as updated.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>, Q: ?Sized + Ord
+    {
+        if let Some((k, _)) = self.map.get_key_value(key) {
+            self.changes.entry(k.clone()).or_insert(State::Updated);
+        }
+        self.map.get_mut(key)
+    }
+
+    /// Removes a key, recording it as removed unless it was inserted since the
+    /// last clear().
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: ?Sized + Ord
+    {
+        let (k, val) = self.map.remove_entry(key)?;
+        match self.changes.entry(k) {
+            Entry::Occupied(entry) => {
+                if *entry.get() == State::Inserted {
+                    entry.remove();
+                } else {
+                    *entry.into_mut() = State::Removed;
+                }
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(State::Removed);
+            },
+        }
+        Some(val)
+    }
+
+    /// Iterate in key order over the keys changed since the last clear() with
+    /// their current value, None if removed.
+    pub fn iter_dirty(&self) -> impl Iterator<Item = (&K, Option<&V>)> {
+        self.changes.keys().map(move |k| (k, self.map.get(k)))
+    }
+
+    /// Iterate in key order over the keys inserted or updated since the last
+    /// clear().
+    pub fn dirty_keys(&self) -> impl Iterator<Item = &K> {
+        self.changes.iter()
+            .filter(|&(_, s)| *s != State::Removed)
+            .map(|(k, _)| k)
+    }
+
+    /// Iterate in key order over the keys removed since the last clear().
+    pub fn removed_keys(&self) -> impl Iterator<Item = &K> {
+        self.changes.iter()
+            .filter(|&(_, s)| *s == State::Removed)
+            .map(|(k, _)| k)
+    }
+
+    /// Returns every change since the last clear() in key order and clears
+    /// them.
+    pub fn drain_changes(&mut self) -> Vec<KeyChange<K>> {
+        mem::take(&mut self.changes).into_iter()
+            .map(|(k, s)| s.into_change(k))
+            .collect()
+    }
+
+    /// Clears the recorded changes.
+    pub fn clear(&mut self) {
+        self.changes.clear();
+    }
+
+    /// Consumes the DirtyBTreeMap, returning the inner BTreeMap.
+    pub fn into_inner(self) -> BTreeMap<K, V> {
+        self.map
+    }
+}
+
+impl<K, V> From<BTreeMap<K, V>> for DirtyBTreeMap<K, V> where K: Ord + Clone {
+    /// Create a new DirtyBTreeMap with every key recorded as inserted.
+    fn from(map: BTreeMap<K, V>) -> DirtyBTreeMap<K, V> {
+        DirtyBTreeMap {
+            changes: map.keys().map(|k| (k.clone(), State::Inserted)).collect(),
+            map,
+        }
+    }
+}
+
+impl<K, V> Deref for DirtyBTreeMap<K, V> {
+    type Target = BTreeMap<K, V>;
+    fn deref(&self) -> &BTreeMap<K, V> {
+        &self.map
+    }
+}
+
+impl<K, V> Default for DirtyBTreeMap<K, V> where K: Ord + Clone {
+    fn default() -> Self {
+        DirtyBTreeMap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtyBTreeMap;
+    use alloc::vec::Vec;
+    use change::KeyChange;
+
+    fn clean() -> DirtyBTreeMap<&'static str, i32> {
+        let mut map = DirtyBTreeMap::new();
+        map.insert("a", 1);
+        map.insert("b", 2);
+        map.insert("c", 3);
+        map.clear();
+        map
+    }
+
+    #[test]
+    fn changes_in_key_order() {
+        let mut map = clean();
+        assert!(!map.dirty());
+        map.insert("d", 4);
+        map.remove("b");
+        *map.get_mut("a").unwrap() += 1;
+        assert!(map.iter_dirty().collect::<Vec<_>>()
+                == vec![(&"a", Some(&2)), (&"b", None), (&"d", Some(&4))]);
+        assert!(map.dirty_keys().collect::<Vec<_>>() == vec![&"a", &"d"]);
+        assert!(map.removed_keys().collect::<Vec<_>>() == vec![&"b"]);
+        assert!(map.drain_changes()
+                == vec![KeyChange::Updated("a"), KeyChange::Removed("b"), KeyChange::Inserted("d")]);
+        assert!(!map.dirty());
+    }
+
+    #[test]
+    fn remove_inserted_is_no_change() {
+        let mut map = clean();
+        map.insert("z", 3);
+        map.remove("z");
+        assert!(!map.dirty());
+        map.remove("a");
+        map.insert("a", 5);
+        assert!(map.drain_changes() == vec![KeyChange::Updated("a")]);
+    }
+}
@@ -0,0 +1,143 @@
+use alloc::vec::Vec;
+use core::ops::{Deref, Range};
+
+use ranges::RangeSet;
+
+/// DirtyBuffer wraps a byte buffer with the coalesced byte ranges written
+/// since the last clear_ranges(), so only the changed spans need uploading.
+/// Use deref (&dirty_buffer[..]) to read the bytes.
+pub struct DirtyBuffer {
+    data: Vec<u8>,
+    ranges: RangeSet,
+}
+
+impl DirtyBuffer {
+    /// Create a new DirtyBuffer with all of its bytes dirty.
+    pub fn new(data: Vec<u8>) -> DirtyBuffer {
+        let mut buffer = DirtyBuffer {
+            data,
+            ranges: RangeSet::new(),
+        };
+        buffer.mark(0..buffer.data.len());
+        buffer
+    }
+
+    /// Returns true if any byte is dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        !self.ranges.as_slice().is_empty()
+    }
+
+    /// Writable slice return of len bytes at offset, marks them dirty.
+    pub fn write_range(&mut self, offset: usize, len: usize) -> &mut [u8] {
+        let range = offset..offset + len;
+        self.mark(range.clone());
+        &mut self.data[range]
+    }
+
+    /// Copies bytes into the buffer at offset, marks them dirty.
+    pub fn write(&mut self, offset: usize, bytes: &[u8]) {
+        self.write_range(offset, bytes.len()).copy_from_slice(bytes);
+    }
+
+    /// Appends bytes to the buffer, marks them dirty.
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        let len = self.data.len();
+        self.data.extend_from_slice(bytes);
+        self.mark(len..len + bytes.len());
+    }
+
+    /// Iterate over the sorted, non overlapping dirty byte ranges.
+    pub fn dirty_ranges(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.ranges.as_slice().iter().cloned()
+    }
+
+    /// Clears the dirty ranges.
+    pub fn clear_ranges(&mut self) {
+        self.ranges.clear();
+    }
+
+    /// Consumes the DirtyBuffer, returning the inner bytes.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+
+    // Empty ranges changed no bytes, so they are not dirty.
+    fn mark(&mut self, range: Range<usize>) {
+        if !range.is_empty() {
+            self.ranges.insert(range);
+        }
+    }
+}
+
+impl From<Vec<u8>> for DirtyBuffer {
+    fn from(data: Vec<u8>) -> DirtyBuffer {
+        DirtyBuffer::new(data)
+    }
+}
+
+impl Deref for DirtyBuffer {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Default for DirtyBuffer {
+    fn default() -> Self {
+        DirtyBuffer::new(Vec::new())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::DirtyBuffer;
+    use alloc::vec::Vec;
+    use core::ops::Range;
+
+    fn clean(len: usize) -> DirtyBuffer {
+        let mut buffer = DirtyBuffer::new(vec![0; len]);
+        buffer.clear_ranges();
+        buffer
+    }
+
+    #[test]
+    fn new_all_dirty() {
+        let buffer = DirtyBuffer::new(vec![0; 8]);
+        assert!(buffer.dirty());
+        assert!(buffer.dirty_ranges().collect::<Vec<Range<usize>>>() == vec![0..8]);
+    }
+
+    #[test]
+    fn writes_coalesce() {
+        let mut buffer = clean(64);
+        assert!(!buffer.dirty());
+        buffer.write(0, &[1, 2, 3, 4]);
+        buffer.write_range(16, 4)[0] = 9;
+        buffer.write(4, &[5]);
+        buffer.write(18, &[7, 7, 7]);
+        assert!(buffer.dirty_ranges().collect::<Vec<Range<usize>>>() == vec![0..5, 16..21]);
+        assert!(buffer[..6] == [1, 2, 3, 4, 5, 0]);
+        assert!(buffer[16] == 9);
+        buffer.clear_ranges();
+        assert!(buffer.dirty_ranges().next().is_none());
+    }
+
+    #[test]
+    fn extend_marks_appended() {
+        let mut buffer = clean(4);
+        buffer.extend_from_slice(&[1, 2]);
+        assert!(buffer.dirty_ranges().collect::<Vec<Range<usize>>>() == vec![4..6]);
+        assert!(buffer.len() == 6);
+    }
+
+    #[test]
+    fn empty_writes_are_clean() {
+        assert!(!DirtyBuffer::default().dirty());
+        let mut buffer = clean(8);
+        buffer.write(6, &[]);
+        buffer.extend_from_slice(&[]);
+        assert!(buffer.write_range(8, 0).is_empty());
+        assert!(!buffer.dirty() && buffer.dirty_ranges().next().is_none());
+    }
+}
@@ -0,0 +1,252 @@
+use std::borrow::Borrow;
+use std::boxed::Box;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::Hash;
+
+type Flush<K, V> = Box<dyn FnMut(&K, &V)>;
+
+struct Entry<V> {
+    value: V,
+    dirty: bool,
+    used: u64,
+}
+
+/// DirtyCache is a write-back LRU map holding at most capacity entries with a
+/// dirty flag per entry, set when the entry is inserted or written.
+/// Evicting a dirty entry, or calling flush_dirty(), passes it to the flush
+/// callback so it may be written back to the backing store.
+/// Entries left dirty when the cache is dropped are not flushed.
+pub struct DirtyCache<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    // Keys by the tick they were last used, least recently used first.
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    capacity: usize,
+    flush: Flush<K, V>,
+}
+
+impl<K, V> DirtyCache<K, V> where K: Eq + Hash + Clone {
+    /// Create a new empty DirtyCache holding at most capacity entries,
+    /// writing back dirty entries with flush.
+    /// Panics if capacity is 0.
+    pub fn new<F>(capacity: usize, flush: F) -> DirtyCache<K, V>
+        where F: 'static + FnMut(&K, &V)
+    {
+        assert!(capacity > 0, "DirtyCache capacity must be at least 1");
+        DirtyCache {
+            entries: HashMap::with_capacity(capacity),
+            order: BTreeMap::new(),
+            tick: 0,
+            capacity,
+            flush: Box::new(flush),
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no entries, false otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the maximum number of entries.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns true if the entry for key is dirty, false otherwise.
+    pub fn dirty<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>, Q: ?Sized + Eq + Hash
+    {
+        self.entries.get(key).map(|e| e.dirty).unwrap_or(false)
+    }
+
+    /// Returns true if any entry is dirty, false otherwise.
+    pub fn any_dirty(&self) -> bool {
+        self.entries.values().any(|e| e.dirty)
+    }
+
+    /// Iterate over the keys of the dirty entries.
+    pub fn dirty_keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter()
+            .filter(|&(_, e)| e.dirty)
+            .map(|(k, _)| k)
+    }
+
+    /// Read the value for key, marking it most recently used.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>, Q: ?Sized + Eq + Hash
+    {
+        let tick = self.next_tick();
+        let (k, entry) = self.entries.get_key_value(key)?;
+        let k = self.order.remove(&entry.used).unwrap_or_else(|| k.clone());
+        self.order.insert(tick, k);
+        let entry = self.entries.get_mut(key)?;
+        entry.used = tick;
+        Some(&entry.value)
+    }
+
+    /// Read the value for key without changing the LRU order.
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>, Q: ?Sized + Eq + Hash
+    {
+        self.entries.get(key).map(|e| &e.value)
+    }
+
+    /// Writable value return, marks the entry dirty and most recently used.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>, Q: ?Sized + Eq + Hash
+    {
+        self.get(key)?;
+        let entry = self.entries.get_mut(key)?;
+        entry.dirty = true;
+        Some(&mut entry.value)
+    }
+
+    /// Inserts a dirty value, evicting the least recently used entry if the
+    /// cache is full. Returns the replaced value.
+    pub fn insert(&mut self, key: K, val: V) -> Option<V> {
+        self.insert_entry(key, val, true)
+    }
+
+    /// Inserts a clean value, such as one just read from the backing store,
+    /// evicting the least recently used entry if the cache is full. Returns
+    /// the replaced value.
+    pub fn insert_clean(&mut self, key: K, val: V) -> Option<V> {
+        self.insert_entry(key, val, false)
+    }
+
+    /// Removes the entry for key without flushing it, returning its value and
+    /// dirty flag.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<(V, bool)>
+        where K: Borrow<Q>, Q: ?Sized + Eq + Hash
+    {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.used);
+        Some((entry.value, entry.dirty))
+    }
+
+    /// Passes every dirty entry to the flush callback, clearing their dirty
+    /// flags. Returns the number of entries flushed.
+    pub fn flush_dirty(&mut self) -> usize {
+        let mut flushed = 0;
+        for (key, entry) in self.entries.iter_mut().filter(|(_, e)| e.dirty) {
+            (self.flush)(key, &entry.value);
+            entry.dirty = false;
+            flushed += 1;
+        }
+        flushed
+    }
+
+    /// Clears the dirty flag of every entry without flushing them.
+    pub fn clear(&mut self) {
+        for entry in self.entries.values_mut() {
+            entry.dirty = false;
+        }
+    }
+
+    fn insert_entry(&mut self, key: K, val: V, dirty: bool) -> Option<V> {
+        let tick = self.next_tick();
+        let old = self.entries.insert(key.clone(), Entry { value: val, dirty, used: tick });
+        match old {
+            Some(ref old) => {
+                self.order.remove(&old.used);
+            },
+            None => {
+                if self.entries.len() > self.capacity {
+                    self.evict();
+                }
+            },
+        }
+        self.order.insert(tick, key);
+        old.map(|e| e.value)
+    }
+
+    fn evict(&mut self) {
+        if let Some((_, key)) = self.order.pop_first() {
+            if let Some(entry) = self.entries.remove(&key) {
+                if entry.dirty {
+                    (self.flush)(&key, &entry.value);
+                }
+            }
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+impl<K, V> fmt::Debug for DirtyCache<K, V> where K: Eq + Hash + fmt::Debug, V: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DirtyCache")
+            .field("entries", &self.order.values()
+                .map(|k| (k, &self.entries[k].value))
+                .collect::<std::vec::Vec<_>>())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtyCache;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    type Written = Rc<RefCell<Vec<(u32, i32)>>>;
+
+    fn cache(capacity: usize) -> (DirtyCache<u32, i32>, Written) {
+        let written = Rc::new(RefCell::new(Vec::new()));
+        let sink = written.clone();
+        let cache = DirtyCache::new(capacity, move |&k, &v| sink.borrow_mut().push((k, v)));
+        (cache, written)
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let (mut cache, written) = cache(2);
+        cache.insert_clean(1, 10);
+        cache.insert(2, 20);
+        assert!(cache.get(&1) == Some(&10));
+        // 2 is least recently used and dirty, so it is written back.
+        cache.insert_clean(3, 30);
+        assert!(*written.borrow() == [(2, 20)]);
+        assert!(cache.peek(&2).is_none() && cache.len() == 2);
+        // 1 is clean, so evicting it writes nothing.
+        cache.insert_clean(4, 40);
+        assert!(written.borrow().len() == 1);
+        assert!(cache.peek(&1).is_none());
+    }
+
+    #[test]
+    fn flush_dirty_writes_back() {
+        let (mut cache, written) = cache(4);
+        cache.insert_clean(1, 10);
+        cache.insert_clean(2, 20);
+        *cache.get_mut(&2).unwrap() += 1;
+        cache.insert(3, 30);
+        assert!(cache.dirty(&2) && !cache.dirty(&1));
+        assert!(cache.flush_dirty() == 2);
+        written.borrow_mut().sort();
+        assert!(*written.borrow() == [(2, 21), (3, 30)]);
+        assert!(!cache.any_dirty());
+        assert!(cache.flush_dirty() == 0);
+    }
+
+    #[test]
+    fn replace_and_remove() {
+        let (mut cache, written) = cache(1);
+        cache.insert_clean(1, 10);
+        assert!(cache.insert(1, 11) == Some(10));
+        assert!(cache.len() == 1 && cache.dirty(&1));
+        assert!(cache.remove(&1) == Some((11, true)));
+        assert!(cache.is_empty() && written.borrow().is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+use core::cell::{BorrowError, BorrowMutError, Cell, Ref, RefCell, RefMut};
+use core::fmt;
+
+use flag::SharedFlag;
+use Dirty;
+
+/// DirtyCell wraps a Copy value of type T with Cell semantics, every method
+/// takes a shared reference so dirty tracking may live inside otherwise
+/// immutable structs. The dirty flag is set on set(), reset on clear().
+/// This is a thin wrapper over `Dirty<Cell<T>, Cell<bool>>`.
+pub struct DirtyCell<T> {
+    inner: Dirty<Cell<T>, Cell<bool>>,
+}
+
+impl<T> DirtyCell<T> where T: Copy {
+    /// Create a new DirtyCell.
+    pub const fn new(val: T) -> DirtyCell<T> {
+        DirtyCell {
+            inner: Dirty::from_flag(Cell::new(val), Cell::new(true)),
+        }
+    }
+
+    /// Create a new clean DirtyCell.
+    pub const fn new_clean(val: T) -> DirtyCell<T> {
+        DirtyCell {
+            inner: Dirty::from_flag(Cell::new(val), Cell::new(false)),
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.inner.dirty()
+    }
+
+    /// Get a copy of the value.
+    pub fn get(&self) -> T {
+        self.inner.read().get()
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&self, val: T) {
+        self.inner.read().set(val);
+        self.inner.flag.mark_shared();
+    }
+
+    /// Clears the dirty flag.
+    pub fn clear(&self) {
+        self.inner.flag.take();
+    }
+
+    /// Get a copy of the value only if modified since last read, clearing the
+    /// dirty flag.
+    pub fn take_dirty(&self) -> Option<T> {
+        self.inner.take_dirty_shared().map(Cell::get)
+    }
+
+    /// Consumes the DirtyCell, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner().into_inner()
+    }
+}
+
+impl<T> Default for DirtyCell<T> where T: Copy + Default {
+    fn default() -> Self {
+        DirtyCell::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for DirtyCell<T> where T: Copy + fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DirtyCell")
+            .field("value", &self.get())
+            .field("dirty", &self.dirty())
+            .finish()
+    }
+}
+
+/// DirtyRefCell wraps a value of type T with RefCell semantics, borrows are
+/// checked at runtime and every method takes a shared reference. The dirty
+/// flag is set on borrow_mut(), reset on clear().
+pub struct DirtyRefCell<T> {
+    value: RefCell<T>,
+    dirty: Cell<bool>,
+}
+
+impl<T> DirtyRefCell<T> {
+    /// Create a new DirtyRefCell.
+    pub const fn new(val: T) -> DirtyRefCell<T> {
+        DirtyRefCell {
+            value: RefCell::new(val),
+            dirty: Cell::new(true),
+        }
+    }
+
+    /// Create a new clean DirtyRefCell.
+    pub const fn new_clean(val: T) -> DirtyRefCell<T> {
+        DirtyRefCell {
+            value: RefCell::new(val),
+            dirty: Cell::new(false),
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty.get()
+    }
+
+    /// Immutably borrows the value, panics if mutably borrowed.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.value.borrow()
+    }
+
+    /// Immutably borrows the value, returning an error if mutably borrowed.
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+        self.value.try_borrow()
+    }
+
+    /// Mutably borrows the value, sets the dirty flag. Panics if borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        let borrowed = self.value.borrow_mut();
+        self.dirty.set(true);
+        borrowed
+    }
+
+    /// Mutably borrows the value, returning an error if borrowed. Sets the
+    /// dirty flag only if the borrow succeeds.
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowMutError> {
+        let borrowed = self.value.try_borrow_mut()?;
+        self.dirty.set(true);
+        Ok(borrowed)
+    }
+
+    /// Writable value return, sets the dirty flag. No runtime check is needed
+    /// as the DirtyRefCell is borrowed mutably.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.dirty.set(true);
+        self.value.get_mut()
+    }
+
+    /// Clears the dirty flag.
+    pub fn clear(&self) {
+        self.dirty.set(false);
+    }
+
+    /// Immutably borrows the value only if modified since last read, clearing
+    /// the dirty flag. Panics if mutably borrowed.
+    pub fn take_dirty(&self) -> Option<Ref<'_, T>> {
+        match self.dirty.replace(false) {
+            true => Some(self.value.borrow()),
+            false => None,
+        }
+    }
+
+    /// Consumes the DirtyRefCell, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T> Default for DirtyRefCell<T> where T: Default {
+    fn default() -> Self {
+        DirtyRefCell::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for DirtyRefCell<T> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DirtyRefCell")
+            .field("value", &self.value)
+            .field("dirty", &self.dirty.get())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirtyCell, DirtyRefCell};
+
+    #[test]
+    fn new_clean() {
+        let dirty = DirtyCell::new_clean(0);
+        assert!(!dirty.dirty());
+    }
+
+    #[test]
+    fn new_dirty() {
+        let dirty = DirtyCell::new(0);
+        assert!(dirty.dirty());
+    }
+
+    #[test]
+    fn set_sets_flag() {
+        let dirty = DirtyCell::new(0);
+        dirty.clear();
+        assert!(!dirty.dirty());
+        dirty.set(1);
+        assert!(dirty.dirty());
+        assert!(dirty.get() == 1);
+    }
+
+    #[test]
+    fn take_dirty_clears_flag() {
+        let dirty = DirtyCell::new(0);
+        assert!(dirty.take_dirty() == Some(0));
+        assert!(!dirty.dirty());
+        assert!(dirty.take_dirty().is_none());
+        dirty.set(2);
+        assert!(dirty.take_dirty() == Some(2));
+    }
+
+    #[test]
+    fn ref_cell_borrow_mut_marks() {
+        let cell = DirtyRefCell::new_clean(1);
+        {
+            let first = cell.borrow();
+            assert!(*first == 1);
+            assert!(cell.try_borrow_mut().is_err());
+        }
+        assert!(!cell.dirty());
+        *cell.borrow_mut() += 1;
+        assert!(cell.dirty());
+        {
+            let taken = cell.take_dirty().unwrap();
+            assert!(*taken == 2);
+            assert!(cell.try_borrow().is_ok());
+        }
+        assert!(cell.take_dirty().is_none());
+        *cell.try_borrow_mut().unwrap() = 3;
+        assert!(cell.into_inner() == 3);
+    }
+}
@@ -0,0 +1,40 @@
+/// A change made to a key of a dirty map since the last clear().
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyChange<K> {
+    /// The key was not present at the last clear() and now is.
+    Inserted(K),
+    /// The key was present at the last clear() and its value was written.
+    Updated(K),
+    /// The key was present at the last clear() and now is not.
+    Removed(K),
+}
+
+/// The change recorded for a key by the dirty maps.
+#[derive(Clone, Copy, PartialEq)]
+pub enum State {
+    Inserted,
+    Updated,
+    Removed,
+}
+
+impl State {
+    /// Returns the state of a key after an insert, given whether the key was
+    /// present and its state before the insert.
+    pub fn after_insert(present: bool, state: Option<State>) -> State {
+        match (present, state) {
+            (_, Some(State::Inserted)) => State::Inserted,
+            (false, Some(State::Removed)) => State::Updated,
+            (false, _) => State::Inserted,
+            (true, _) => State::Updated,
+        }
+    }
+
+    /// Returns the KeyChange reported for key in this state.
+    pub fn into_change<K>(self, key: K) -> KeyChange<K> {
+        match self {
+            State::Inserted => KeyChange::Inserted(key),
+            State::Updated => KeyChange::Updated(key),
+            State::Removed => KeyChange::Removed(key),
+        }
+    }
+}
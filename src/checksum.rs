@@ -0,0 +1,180 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+/// Checksum computes a checksum of a byte buffer for ChecksumDirty.
+pub trait Checksum {
+    /// Returns the checksum of bytes.
+    fn checksum(bytes: &[u8]) -> u64;
+}
+
+/// 64 bit FNV-1a, needs no dependencies.
+pub struct Fnv1a;
+
+impl Checksum for Fnv1a {
+    fn checksum(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+            (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+}
+
+/// CRC32 as computed by crc32fast.
+#[cfg(feature = "crc32")]
+pub struct Crc32;
+
+#[cfg(feature = "crc32")]
+impl Checksum for Crc32 {
+    fn checksum(bytes: &[u8]) -> u64 {
+        crc32fast::hash(bytes) as u64
+    }
+}
+
+/// ChecksumDirty wraps a byte buffer of type B like Dirty and records a
+/// checksum of the bytes at each flush, so a buffer rewritten with identical
+/// content is not flushed again.
+/// A new ChecksumDirty starts dirty with no checksum recorded.
+pub struct ChecksumDirty<B, C = Fnv1a> where B: AsRef<[u8]>, C: Checksum {
+    value: B,
+    dirty: bool,
+    checksum: Option<u64>,
+    checksummer: PhantomData<C>,
+}
+
+impl<B, C> ChecksumDirty<B, C> where B: AsRef<[u8]>, C: Checksum {
+    /// Create a new ChecksumDirty.
+    pub fn new(val: B) -> ChecksumDirty<B, C> {
+        ChecksumDirty {
+            value: val,
+            dirty: true,
+            checksum: None,
+            checksummer: PhantomData,
+        }
+    }
+
+    /// Create a new clean ChecksumDirty, recording the checksum of val.
+    pub fn new_clean(val: B) -> ChecksumDirty<B, C> {
+        ChecksumDirty {
+            checksum: Some(C::checksum(val.as_ref())),
+            value: val,
+            dirty: false,
+            checksummer: PhantomData,
+        }
+    }
+
+    /// Returns true if written since the last flush, false otherwise. The
+    /// bytes may still be unchanged, see changed().
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns true if the checksum of the bytes differs from the one
+    /// recorded at the last flush, false otherwise.
+    pub fn changed(&self) -> bool {
+        self.checksum != Some(C::checksum(self.value.as_ref()))
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut B {
+        self.dirty = true;
+        &mut self.value
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: B) {
+        self.value = val;
+        self.dirty = true;
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &B {
+        &self.value
+    }
+
+    /// Clears the dirty flag without recording a checksum.
+    pub fn clear(&mut self) {
+        self.dirty = false;
+    }
+
+    /// If dirty and the checksum of the bytes changed, run f with the bytes
+    /// and record the checksum only if it returns Ok. Returns true if f ran
+    /// and succeeded, false if clean or unchanged, clearing the dirty flag.
+    pub fn flush<E, F>(&mut self, f: F) -> Result<bool, E>
+        where F: FnOnce(&[u8]) -> Result<(), E>
+    {
+        if !self.dirty {
+            return Ok(false);
+        }
+        let checksum = C::checksum(self.value.as_ref());
+        if self.checksum == Some(checksum) {
+            self.dirty = false;
+            return Ok(false);
+        }
+        f(self.value.as_ref())?;
+        self.checksum = Some(checksum);
+        self.dirty = false;
+        Ok(true)
+    }
+
+    /// Consumes the ChecksumDirty, returning the inner value.
+    pub fn into_inner(self) -> B {
+        self.value
+    }
+}
+
+impl<B, C> Deref for ChecksumDirty<B, C> where B: AsRef<[u8]>, C: Checksum {
+    type Target = B;
+    fn deref(&self) -> &B {
+        &self.value
+    }
+}
+
+impl<B, C> Default for ChecksumDirty<B, C> where B: AsRef<[u8]> + Default, C: Checksum {
+    fn default() -> Self {
+        ChecksumDirty::new(B::default())
+    }
+}
+
+impl<B, C> fmt::Debug for ChecksumDirty<B, C> where B: AsRef<[u8]> + fmt::Debug, C: Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChecksumDirty")
+            .field("value", &self.value)
+            .field("dirty", &self.dirty)
+            .field("checksum", &self.checksum)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Checksum, ChecksumDirty, Fnv1a};
+
+    #[test]
+    fn identical_rewrite_not_flushed() {
+        let mut buffer: ChecksumDirty<[u8; 4]> = ChecksumDirty::new([1, 2, 3, 4]);
+        let mut sends = 0;
+        assert!(buffer.flush(|_| { sends += 1; Ok::<(), ()>(()) }) == Ok(true));
+        buffer.set([1, 2, 3, 4]);
+        assert!(buffer.dirty() && !buffer.changed());
+        assert!(buffer.flush(|_| { sends += 1; Ok::<(), ()>(()) }) == Ok(false));
+        assert!(!buffer.dirty());
+        buffer.write()[0] = 9;
+        assert!(buffer.flush(|bytes| { sends += bytes[0]; Ok::<(), ()>(()) }) == Ok(true));
+        assert!(sends == 10);
+    }
+
+    #[test]
+    fn failed_flush_stays_dirty() {
+        let mut buffer: ChecksumDirty<&[u8]> = ChecksumDirty::new_clean(b"abc");
+        buffer.set(b"abd");
+        assert!(buffer.flush(|_| Err(())) == Err(()));
+        assert!(buffer.dirty() && buffer.changed());
+        assert!(Fnv1a::checksum(b"") == 0xcbf2_9ce4_8422_2325);
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn crc32() {
+        assert!(super::Crc32::checksum(b"123456789") == 0xcbf4_3926);
+    }
+}
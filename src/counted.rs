@@ -0,0 +1,145 @@
+use core::fmt;
+use core::ops::Deref;
+
+/// CountedDirty wraps a value of type T like Dirty and counts the writes
+/// since the last clear(), e.g. to choose between an incremental and a full
+/// re-sync.
+pub struct CountedDirty<T> {
+    value: T,
+    dirty: bool,
+    writes: usize,
+}
+
+impl<T> CountedDirty<T> {
+    /// Create a new CountedDirty, dirty with no writes counted.
+    pub fn new(val: T) -> CountedDirty<T> {
+        CountedDirty {
+            value: val,
+            dirty: true,
+            writes: 0,
+        }
+    }
+
+    /// Create a new clean CountedDirty.
+    pub fn new_clean(val: T) -> CountedDirty<T> {
+        CountedDirty {
+            value: val,
+            dirty: false,
+            writes: 0,
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the number of writes since the last clear().
+    pub fn writes(&self) -> usize {
+        self.writes
+    }
+
+    /// Writable value return, sets the dirty flag and counts a write.
+    pub fn write(&mut self) -> &mut T {
+        self.mark();
+        &mut self.value
+    }
+
+    /// Set the value, sets the dirty flag and counts a write.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.mark();
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Clears the dirty flag and write count.
+    pub fn clear(&mut self) {
+        self.dirty = false;
+        self.writes = 0;
+    }
+
+    /// Read the value only if modified since last read.
+    pub fn read_dirty(&self) -> Option<&T> {
+        match self.dirty {
+            true => Some(&self.value),
+            false => None,
+        }
+    }
+
+    /// Consumes the CountedDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    fn mark(&mut self) {
+        self.dirty = true;
+        self.writes = self.writes.saturating_add(1);
+    }
+}
+
+impl<T> Deref for CountedDirty<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Default for CountedDirty<T> where T: Default {
+    fn default() -> Self {
+        CountedDirty::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for CountedDirty<T> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CountedDirty")
+            .field("value", &self.value)
+            .field("dirty", &self.dirty)
+            .field("writes", &self.writes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CountedDirty;
+
+    #[test]
+    fn new_dirty_without_writes() {
+        let dirty = CountedDirty::new(0);
+        assert!(dirty.dirty());
+        assert!(dirty.writes() == 0);
+    }
+
+    #[test]
+    fn new_clean() {
+        let dirty = CountedDirty::new_clean(0);
+        assert!(!dirty.dirty());
+        assert!(dirty.writes() == 0);
+    }
+
+    #[test]
+    fn counts_writes_until_clear() {
+        let mut dirty = CountedDirty::new(0);
+        *dirty.write() += 1;
+        *dirty.write() += 1;
+        dirty.set(5);
+        assert!(dirty.writes() == 3);
+        dirty.clear();
+        assert!(!dirty.dirty());
+        assert!(dirty.writes() == 0);
+        dirty.set(6);
+        assert!(dirty.writes() == 1);
+    }
+
+    #[test]
+    fn debug_shows_count() {
+        let mut dirty = CountedDirty::new_clean(1);
+        dirty.set(2);
+        assert!(std::format!("{:?}", dirty) == "CountedDirty { value: 2, dirty: true, writes: 1 }");
+    }
+}
@@ -0,0 +1,40 @@
+use alloc::borrow::{Cow, ToOwned};
+
+use flag::DirtyFlag;
+use Dirty;
+
+impl<'a, B, D> Dirty<Cow<'a, B>, D> where B: 'a + ToOwned + ?Sized, D: DirtyFlag {
+    /// Writable owned value return, cloning the borrowed value first if
+    /// needed, sets the dirty flag.
+    pub fn to_mut(&mut self) -> &mut B::Owned {
+        self.write().to_mut()
+    }
+
+    /// Returns true if the value is borrowed, false otherwise.
+    pub fn is_borrowed(&self) -> bool {
+        match *self.read() {
+            Cow::Borrowed(_) => true,
+            Cow::Owned(_) => false,
+        }
+    }
+
+    /// Returns true if the value is owned, false otherwise.
+    pub fn is_owned(&self) -> bool {
+        !self.is_borrowed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::Cow;
+    use Dirty;
+
+    #[test]
+    fn to_mut_marks() {
+        let mut name = Dirty::new_clean(Cow::Borrowed("default"));
+        assert!(name.is_borrowed());
+        name.to_mut().push_str("-override");
+        assert!(name.dirty() && name.is_owned());
+        assert!(*name == "default-override");
+    }
+}
@@ -0,0 +1,136 @@
+use core::fmt;
+use core::ops::{Deref, Sub};
+
+/// DeltaDirty wraps a numeric value of type T like Dirty and keeps the value
+/// it had at the last clear(), so counters may be sent as increments instead
+/// of absolute values.
+/// A new DeltaDirty starts dirty with a delta of zero.
+pub struct DeltaDirty<T> where T: Sub<Output = T> + Clone {
+    value: T,
+    base: T,
+    dirty: bool,
+}
+
+impl<T> DeltaDirty<T> where T: Sub<Output = T> + Clone {
+    /// Create a new DeltaDirty.
+    pub fn new(val: T) -> DeltaDirty<T> {
+        DeltaDirty {
+            base: val.clone(),
+            value: val,
+            dirty: true,
+        }
+    }
+
+    /// Create a new clean DeltaDirty.
+    pub fn new_clean(val: T) -> DeltaDirty<T> {
+        DeltaDirty {
+            base: val.clone(),
+            value: val,
+            dirty: false,
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.value
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.dirty = true;
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Read the value as it was at the last clear().
+    pub fn base(&self) -> &T {
+        &self.base
+    }
+
+    /// Returns the value less the value at the last clear().
+    pub fn delta(&self) -> T {
+        self.value.clone() - self.base.clone()
+    }
+
+    /// Clears the dirty flag, the delta starts again from the value.
+    pub fn clear(&mut self) {
+        self.base.clone_from(&self.value);
+        self.dirty = false;
+    }
+
+    /// Returns the delta only if modified since the last clear(), clearing
+    /// the dirty flag.
+    pub fn take_delta(&mut self) -> Option<T> {
+        match self.dirty {
+            true => {
+                let delta = self.delta();
+                self.clear();
+                Some(delta)
+            },
+            false => None,
+        }
+    }
+
+    /// Consumes the DeltaDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for DeltaDirty<T> where T: Sub<Output = T> + Clone {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Default for DeltaDirty<T> where T: Sub<Output = T> + Clone + Default {
+    fn default() -> Self {
+        DeltaDirty::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for DeltaDirty<T> where T: Sub<Output = T> + Clone + fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DeltaDirty")
+            .field("value", &self.value)
+            .field("base", &self.base)
+            .field("dirty", &self.dirty)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeltaDirty;
+
+    #[test]
+    fn increments_since_clear() {
+        let mut requests = DeltaDirty::new_clean(10i64);
+        assert!(requests.take_delta().is_none());
+        *requests.write() += 3;
+        *requests.write() += 4;
+        assert!(requests.delta() == 7);
+        assert!(requests.take_delta() == Some(7));
+        assert!(*requests.base() == 17 && requests.delta() == 0);
+        requests.set(15);
+        assert!(requests.take_delta() == Some(-2));
+    }
+
+    #[test]
+    fn new_dirty_with_zero_delta() {
+        let mut value = DeltaDirty::new(1.5f64);
+        assert!(value.dirty());
+        assert!(value.take_delta() == Some(0.0));
+    }
+}
@@ -0,0 +1,164 @@
+use core::fmt;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Epoch is a shared counter advanced once per frame, usually a static, which
+/// EpochDirty values read on every write.
+/// Only available on targets with 64 bit atomics.
+pub struct Epoch {
+    current: AtomicU64,
+}
+
+impl Epoch {
+    /// Create a new Epoch starting at 0.
+    pub const fn new() -> Epoch {
+        Epoch {
+            current: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the current epoch.
+    pub fn current(&self) -> u64 {
+        self.current.load(Ordering::Acquire)
+    }
+
+    /// Advances to the next epoch, returning it.
+    pub fn advance(&self) -> u64 {
+        self.current.fetch_add(1, Ordering::AcqRel) + 1
+    }
+}
+
+impl Default for Epoch {
+    fn default() -> Self {
+        Epoch::new()
+    }
+}
+
+impl fmt::Debug for Epoch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Epoch")
+            .field("current", &self.current())
+            .finish()
+    }
+}
+
+/// EpochDirty wraps a value of type T with the epoch of its last write, so any
+/// number of systems may each compare against the epoch they last ran at
+/// without coordinating who clears a flag.
+/// A system should store the current epoch each time it runs and pass it to
+/// changed_since() on its next run. A write made during the stored epoch is
+/// reported again on that next run, it may be seen twice but is never missed.
+pub struct EpochDirty<'a, T> {
+    value: T,
+    written: u64,
+    epoch: &'a Epoch,
+}
+
+impl<'a, T> EpochDirty<'a, T> {
+    /// Create a new EpochDirty written at the current epoch.
+    pub fn new(val: T, epoch: &'a Epoch) -> EpochDirty<'a, T> {
+        EpochDirty {
+            value: val,
+            written: epoch.current(),
+            epoch,
+        }
+    }
+
+    /// Returns the epoch of the last write.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Returns true if the value was written during or after epoch, false
+    /// otherwise.
+    pub fn changed_since(&self, epoch: u64) -> bool {
+        self.written >= epoch
+    }
+
+    /// Writable value return, records the current epoch as the last write.
+    pub fn write(&mut self) -> &mut T {
+        self.written = self.epoch.current();
+        &mut self.value
+    }
+
+    /// Set the value, records the current epoch as the last write.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.written = self.epoch.current();
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Read the value only if written during or after epoch.
+    pub fn read_if_changed(&self, epoch: u64) -> Option<&T> {
+        match self.changed_since(epoch) {
+            true => Some(&self.value),
+            false => None,
+        }
+    }
+
+    /// Consumes the EpochDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<'a, T> Deref for EpochDirty<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T> fmt::Debug for EpochDirty<'a, T> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EpochDirty")
+            .field("value", &self.value)
+            .field("written", &self.written)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Epoch, EpochDirty};
+
+    static FRAME: Epoch = Epoch::new();
+
+    #[test]
+    fn observers_compare_epochs() {
+        let epoch = Epoch::new();
+        let mut position = EpochDirty::new((0, 0), &epoch);
+        epoch.advance();
+        let physics_seen = epoch.current();
+        position.write().0 = 5;
+        let render_seen = epoch.advance();
+        assert!(position.changed_since(physics_seen));
+        assert!(!position.changed_since(render_seen));
+        position.set((1, 1));
+        assert!(position.written() == 2);
+        assert!(position.read_if_changed(render_seen) == Some(&(1, 1)));
+        assert!(position.read_if_changed(3).is_none());
+    }
+
+    #[test]
+    fn same_epoch_write_is_seen() {
+        let epoch = Epoch::new();
+        let mut value = EpochDirty::new(0, &epoch);
+        epoch.advance();
+        // The system runs, then the value is written later in the same epoch.
+        let seen = epoch.current();
+        value.set(1);
+        epoch.advance();
+        assert!(value.changed_since(seen));
+    }
+
+    #[test]
+    fn static_epoch() {
+        let value = EpochDirty::new(1, &FRAME);
+        assert!(!value.changed_since(FRAME.advance()));
+    }
+}
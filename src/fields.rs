@@ -0,0 +1,64 @@
+#[cfg(feature = "prost")]
+use alloc::string::ToString;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "prost")]
+use prost_types::FieldMask;
+
+/// DirtyFields is implemented by the tracker type generated with
+/// #[derive(DirtyFields)], which holds a struct alongside one dirty flag
+/// per field.
+///
+/// For a struct `User` with a field `name` the derive generates a `DirtyUser`
+/// tracker with `set_name()` and `name_mut()`, both of which set only the
+/// `name` flag. The tracker derefs to the struct for reads.
+///
+/// Enums are tracked with a `variant` flag followed by one flag per field of
+/// every variant, named `Variant.field`.
+pub trait DirtyFields {
+    /// The tracked struct.
+    type Inner;
+
+    /// Names of the tracked fields in declaration order.
+    const FIELDS: &'static [&'static str];
+
+    /// Read the tracked struct.
+    fn inner(&self) -> &Self::Inner;
+
+    /// Returns true if the field at index (into FIELDS) is dirty.
+    fn field_dirty(&self, index: usize) -> bool;
+
+    /// Clears the dirty flag of every field.
+    fn clear_all(&mut self);
+
+    /// Returns true if any field is dirty, false otherwise.
+    fn dirty(&self) -> bool {
+        (0..Self::FIELDS.len()).any(|i| self.field_dirty(i))
+    }
+
+    /// Names of the dirty fields in declaration order.
+    #[cfg(feature = "alloc")]
+    fn dirty_fields(&self) -> Vec<&'static str> {
+        Self::FIELDS.iter()
+            .enumerate()
+            .filter(|&(i, _)| self.field_dirty(i))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+/// StructFields marks the DirtyFields trackers of structs, whose FIELDS are
+/// the members of the struct, so the dirty fields may be named in output
+/// formats such as MergePatch and SqlUpdate.
+/// Enum trackers don't implement it, their `Variant.field` names are not
+/// members of the serialized enum.
+pub trait StructFields: DirtyFields {
+    /// Returns a google.protobuf.FieldMask of the dirty fields for partial
+    /// update calls. Fields are assumed to be named as in the message.
+    #[cfg(feature = "prost")]
+    fn field_mask(&self) -> FieldMask {
+        FieldMask {
+            paths: self.dirty_fields().into_iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
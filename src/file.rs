@@ -0,0 +1,149 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use Dirty;
+
+/// FileDirty wraps a value of type T like Dirty bound to a JSON file, so the
+/// file is only rewritten when the value was written since it was loaded or
+/// last saved.
+pub struct FileDirty<T> where T: Serialize + DeserializeOwned {
+    value: Dirty<T>,
+    path: PathBuf,
+}
+
+impl<T> FileDirty<T> where T: Serialize + DeserializeOwned {
+    /// Create a new dirty FileDirty to be saved at path.
+    pub fn new<P: Into<PathBuf>>(val: T, path: P) -> FileDirty<T> {
+        FileDirty {
+            value: Dirty::new(val),
+            path: path.into(),
+        }
+    }
+
+    /// Loads the value from the file at path, the FileDirty starts clean.
+    pub fn load<P: Into<PathBuf>>(path: P) -> io::Result<FileDirty<T>> {
+        let path = path.into();
+        let reader = BufReader::new(File::open(&path)?);
+        let val = serde_json::from_reader(reader)?;
+        Ok(FileDirty {
+            value: Dirty::new_clean(val),
+            path,
+        })
+    }
+
+    /// Loads the value from the file at path, or uses the default value
+    /// starting dirty if there is no file.
+    pub fn load_or_default<P: Into<PathBuf>>(path: P) -> io::Result<FileDirty<T>>
+        where T: Default
+    {
+        let path = path.into();
+        match FileDirty::load(&path) {
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                Ok(FileDirty::new(T::default(), path))
+            },
+            loaded => loaded,
+        }
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.value.dirty()
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.value.write()
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        self.value.set(val);
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        self.value.read()
+    }
+
+    /// If dirty, writes the value to a temporary file next to the path and
+    /// renames it over the path, so the file is never left half written.
+    /// Clears the dirty flag only if the save succeeds. Returns true if
+    /// saved, false if clean.
+    pub fn save_if_dirty(&mut self) -> io::Result<bool> {
+        let path = &self.path;
+        self.value.flush(|val| {
+            let mut tmp = path.clone().into_os_string();
+            tmp.push(".tmp");
+            let file = File::create(&tmp)?;
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer_pretty(&mut writer, val)?;
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+            fs::rename(&tmp, path)
+        })
+    }
+
+    /// Consumes the FileDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T> Deref for FileDirty<T> where T: Serialize + DeserializeOwned {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value.read()
+    }
+}
+
+impl<T> fmt::Debug for FileDirty<T> where T: Serialize + DeserializeOwned + fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FileDirty")
+            .field("value", self.value.read())
+            .field("dirty", &self.value.dirty())
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileDirty;
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs;
+    use std::string::String;
+
+    type Settings = BTreeMap<String, u32>;
+
+    #[test]
+    fn save_only_when_edited() {
+        let path = env::temp_dir().join(std::format!("dirty-settings-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut settings: FileDirty<Settings> = FileDirty::load_or_default(&path).unwrap();
+        assert!(settings.dirty());
+        settings.write().insert(String::from("volume"), 7);
+        assert!(settings.save_if_dirty().unwrap());
+        assert!(!settings.save_if_dirty().unwrap());
+
+        let mut loaded: FileDirty<Settings> = FileDirty::load(&path).unwrap();
+        assert!(!loaded.dirty() && loaded["volume"] == 7);
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        assert!(!loaded.save_if_dirty().unwrap());
+        assert!(fs::metadata(&path).unwrap().modified().unwrap() == modified);
+        loaded.write().insert(String::from("volume"), 3);
+        assert!(loaded.save_if_dirty().unwrap());
+        assert!(FileDirty::<Settings>::load(loaded.path()).unwrap().into_inner()["volume"] == 3);
+        fs::remove_file(&path).unwrap();
+    }
+}
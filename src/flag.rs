@@ -0,0 +1,146 @@
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// DirtyFlag is the storage of the dirty flag of a Dirty, letting one Dirty
+/// serve plain, interior mutable, atomic and versioned flags.
+pub trait DirtyFlag {
+    /// Create a new flag.
+    fn new(dirty: bool) -> Self;
+
+    /// Returns true if dirty, false otherwise.
+    fn get(&self) -> bool;
+
+    /// Sets the flag.
+    fn mark(&mut self);
+
+    /// Clears the flag.
+    fn clear(&mut self);
+}
+
+/// SharedFlag is a DirtyFlag which may also be set and cleared through a
+/// shared reference, so a Dirty may be cleared by readers holding only &Dirty.
+pub trait SharedFlag: DirtyFlag {
+    /// Sets the flag.
+    fn mark_shared(&self);
+
+    /// Clears the flag, returning true if it was set.
+    fn take(&self) -> bool;
+}
+
+impl DirtyFlag for bool {
+    fn new(dirty: bool) -> bool {
+        dirty
+    }
+
+    fn get(&self) -> bool {
+        *self
+    }
+
+    fn mark(&mut self) {
+        *self = true;
+    }
+
+    fn clear(&mut self) {
+        *self = false;
+    }
+}
+
+impl DirtyFlag for Cell<bool> {
+    fn new(dirty: bool) -> Cell<bool> {
+        Cell::new(dirty)
+    }
+
+    fn get(&self) -> bool {
+        Cell::get(self)
+    }
+
+    fn mark(&mut self) {
+        self.set(true);
+    }
+
+    fn clear(&mut self) {
+        self.set(false);
+    }
+}
+
+impl SharedFlag for Cell<bool> {
+    fn mark_shared(&self) {
+        self.set(true);
+    }
+
+    fn take(&self) -> bool {
+        self.replace(false)
+    }
+}
+
+impl DirtyFlag for AtomicBool {
+    fn new(dirty: bool) -> AtomicBool {
+        AtomicBool::new(dirty)
+    }
+
+    fn get(&self) -> bool {
+        self.load(Ordering::Acquire)
+    }
+
+    fn mark(&mut self) {
+        *self.get_mut() = true;
+    }
+
+    fn clear(&mut self) {
+        *self.get_mut() = false;
+    }
+}
+
+impl SharedFlag for AtomicBool {
+    fn mark_shared(&self) {
+        self.store(true, Ordering::Release);
+    }
+
+    fn take(&self) -> bool {
+        self.swap(false, Ordering::AcqRel)
+    }
+}
+
+/// The low bit is the dirty flag and the remaining bits count the marks, so
+/// the version changes on every write, see Dirty::version().
+impl DirtyFlag for u64 {
+    fn new(dirty: bool) -> u64 {
+        dirty as u64
+    }
+
+    fn get(&self) -> bool {
+        *self & 1 != 0
+    }
+
+    fn mark(&mut self) {
+        *self = self.wrapping_add(2) | 1;
+    }
+
+    fn clear(&mut self) {
+        *self &= !1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirtyFlag, SharedFlag};
+    use core::cell::Cell;
+
+    #[test]
+    fn version_counts_marks() {
+        let mut flag = <u64 as DirtyFlag>::new(false);
+        flag.mark();
+        flag.mark();
+        assert!(flag.get() && flag >> 1 == 2);
+        flag.clear();
+        assert!(!flag.get() && flag >> 1 == 2);
+    }
+
+    #[test]
+    fn shared_take() {
+        let flag = <Cell<bool> as DirtyFlag>::new(false);
+        flag.mark_shared();
+        assert!(flag.take());
+        assert!(!flag.take());
+    }
+}
@@ -0,0 +1,55 @@
+use std::io;
+
+#[cfg(feature = "json")]
+use flag::DirtyFlag;
+#[cfg(feature = "json")]
+use serde::Serialize;
+#[cfg(feature = "json")]
+use Dirty;
+
+/// FlushTo is implemented by dirty values which may be written into a sink
+/// of type S, so persistence layers may flush heterogeneous values through
+/// one interface such as `Vec<&mut dyn FlushTo<File>>`.
+pub trait FlushTo<S> where S: ?Sized {
+    /// Writes the value into sink if dirty, clearing the dirty flag only if
+    /// the write succeeds. Returns true if written, false if clean.
+    fn flush_to(&mut self, sink: &mut S) -> io::Result<bool>;
+}
+
+/// Writes the value as a line of newline delimited JSON.
+#[cfg(feature = "json")]
+impl<T, D, W> FlushTo<W> for Dirty<T, D> where T: Serialize, D: DirtyFlag, W: io::Write {
+    fn flush_to(&mut self, sink: &mut W) -> io::Result<bool> {
+        self.flush(|val| {
+            serde_json::to_writer(&mut *sink, val)?;
+            sink.write_all(b"\n")
+        })
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::FlushTo;
+    use std::string::String;
+    use std::vec::Vec;
+    use Dirty;
+
+    #[test]
+    fn flush_heterogeneous() {
+        let mut count = Dirty::new(1);
+        let mut name = Dirty::new_clean(String::from("a"));
+        let mut sink = Vec::new();
+        {
+            let mut values: [&mut dyn FlushTo<Vec<u8>>; 2] = [&mut count, &mut name];
+            for value in values.iter_mut() {
+                value.flush_to(&mut sink).unwrap();
+            }
+        }
+        assert!(sink == b"1\n");
+        assert!(count.is_clean());
+        name.write().push('b');
+        assert!(name.flush_to(&mut sink).unwrap());
+        assert!(!name.flush_to(&mut sink).unwrap());
+        assert!(sink == b"1\n\"ab\"\n");
+    }
+}
@@ -0,0 +1,200 @@
+//! A pull based dependency graph of values, where writing a source marks
+//! every value derived from it stale and stale values are recomputed in
+//! dependency order when read.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// Identifies a signal of a Graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SignalId(usize);
+
+type Compute<'a, T> = Box<dyn FnMut(&[&T]) -> T + 'a>;
+
+struct Signal<T> {
+    value: T,
+    stale: bool,
+    deps: Vec<SignalId>,
+    dependents: Vec<SignalId>,
+}
+
+/// Graph holds source signals, set directly, and derived signals, computed
+/// from the values of their dependencies.
+/// Dependencies must exist when a derived signal is added, so the graph is
+/// acyclic and ids are in dependency order.
+pub struct Graph<'a, T> {
+    signals: Vec<Signal<T>>,
+    computes: Vec<Option<Compute<'a, T>>>,
+}
+
+impl<'a, T> Graph<'a, T> {
+    /// Create a new empty Graph.
+    pub fn new() -> Graph<'a, T> {
+        Graph {
+            signals: Vec::new(),
+            computes: Vec::new(),
+        }
+    }
+
+    /// Returns the number of signals.
+    pub fn len(&self) -> usize {
+        self.signals.len()
+    }
+
+    /// Returns true if there are no signals, false otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.signals.is_empty()
+    }
+
+    /// Adds a source signal.
+    pub fn source(&mut self, val: T) -> SignalId {
+        self.push(val, Vec::new(), None)
+    }
+
+    /// Adds a signal derived from deps, computing it with the values of deps
+    /// in the same order.
+    pub fn derived<F>(&mut self, deps: &[SignalId], mut compute: F) -> SignalId
+        where F: FnMut(&[&T]) -> T + 'a
+    {
+        for d in deps {
+            self.refresh(*d);
+        }
+        let val = {
+            let args: Vec<&T> = deps.iter().map(|d| &self.signals[d.0].value).collect();
+            compute(&args)
+        };
+        let id = self.push(val, deps.to_vec(), Some(Box::new(compute)));
+        for d in deps {
+            self.signals[d.0].dependents.push(id);
+        }
+        id
+    }
+
+    /// Returns true if id is a source signal, false otherwise.
+    pub fn is_source(&self, id: SignalId) -> bool {
+        self.computes[id.0].is_none()
+    }
+
+    /// Returns true if id is derived and must be recomputed before it is
+    /// read, false otherwise.
+    pub fn stale(&self, id: SignalId) -> bool {
+        self.signals[id.0].stale
+    }
+
+    /// Writable source value return, marks every signal derived from it
+    /// stale.
+    pub fn write(&mut self, id: SignalId) -> &mut T {
+        assert!(self.is_source(id), "only source signals may be written");
+        self.invalidate(id);
+        &mut self.signals[id.0].value
+    }
+
+    /// Set a source value, marks every signal derived from it stale.
+    pub fn set(&mut self, id: SignalId, val: T) {
+        *self.write(id) = val;
+    }
+
+    /// Read the value of id, recomputing it and its stale dependencies first.
+    pub fn get(&mut self, id: SignalId) -> &T {
+        self.refresh(id);
+        &self.signals[id.0].value
+    }
+
+    /// Read the value of id without recomputing, which may be stale.
+    pub fn peek(&self, id: SignalId) -> &T {
+        &self.signals[id.0].value
+    }
+
+    fn push(&mut self, val: T, deps: Vec<SignalId>, compute: Option<Compute<'a, T>>) -> SignalId {
+        let id = SignalId(self.signals.len());
+        self.signals.push(Signal {
+            value: val,
+            stale: false,
+            deps,
+            dependents: Vec::new(),
+        });
+        self.computes.push(compute);
+        id
+    }
+
+    fn invalidate(&mut self, id: SignalId) {
+        let mut pending = self.signals[id.0].dependents.clone();
+        while let Some(d) = pending.pop() {
+            let signal = &mut self.signals[d.0];
+            // Stale signals already had their dependents marked.
+            if !signal.stale {
+                signal.stale = true;
+                pending.extend_from_slice(&signal.dependents);
+            }
+        }
+    }
+
+    fn refresh(&mut self, id: SignalId) {
+        if !self.signals[id.0].stale {
+            return;
+        }
+        for i in 0..self.signals[id.0].deps.len() {
+            let d = self.signals[id.0].deps[i];
+            self.refresh(d);
+        }
+        let val = {
+            let signals = &self.signals;
+            let args: Vec<&T> = signals[id.0].deps.iter().map(|d| &signals[d.0].value).collect();
+            (self.computes[id.0].as_mut().unwrap())(&args)
+        };
+        let signal = &mut self.signals[id.0];
+        signal.value = val;
+        signal.stale = false;
+    }
+}
+
+impl<'a, T> Default for Graph<'a, T> {
+    fn default() -> Self {
+        Graph::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Graph;
+    use std::cell::Cell;
+
+    #[test]
+    fn derived_recomputed_on_read() {
+        let runs = Cell::new(0);
+        let mut graph = Graph::new();
+        let a = graph.source(1);
+        let b = graph.source(2);
+        let sum = graph.derived(&[a, b], |v| {
+            runs.set(runs.get() + 1);
+            v[0] + v[1]
+        });
+        let double = graph.derived(&[sum], |v| v[0] * 2);
+        assert!(*graph.get(double) == 6);
+        assert!(runs.get() == 1);
+        graph.set(a, 5);
+        assert!(graph.stale(sum) && graph.stale(double));
+        assert!(*graph.peek(double) == 6);
+        assert!(*graph.get(double) == 14);
+        assert!(*graph.get(sum) == 7);
+        assert!(runs.get() == 2);
+    }
+
+    #[test]
+    fn diamond_computes_once() {
+        let runs = Cell::new(0);
+        let mut graph = Graph::new();
+        let a = graph.source(1);
+        let left = graph.derived(&[a], |v| v[0] + 1);
+        let right = graph.derived(&[a], |v| v[0] * 10);
+        let join = graph.derived(&[left, right], |v| {
+            runs.set(runs.get() + 1);
+            v[0] + v[1]
+        });
+        *graph.write(a) += 1;
+        assert!(*graph.get(join) == 23);
+        assert!(*graph.get(join) == 23);
+        assert!(runs.get() == 2);
+        assert!(!graph.is_source(join));
+    }
+}
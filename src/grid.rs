@@ -0,0 +1,216 @@
+use alloc::vec::Vec;
+use core::cmp;
+use core::mem;
+
+/// A rectangle of cells in a DirtyGrid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rect {
+    /// Left column.
+    pub x: usize,
+    /// Top row.
+    pub y: usize,
+    /// Number of columns.
+    pub width: usize,
+    /// Number of rows.
+    pub height: usize,
+}
+
+impl Rect {
+    /// Create a new Rect.
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns true if the rects overlap or touch, false otherwise.
+    pub fn touches(&self, other: &Rect) -> bool {
+        self.x <= other.x + other.width && other.x <= self.x + self.width &&
+        self.y <= other.y + other.height && other.y <= self.y + self.height
+    }
+
+    /// Returns the smallest rect containing both rects.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = cmp::min(self.x, other.x);
+        let y = cmp::min(self.y, other.y);
+        Rect {
+            x,
+            y,
+            width: cmp::max(self.x + self.width, other.x + other.width) - x,
+            height: cmp::max(self.y + self.height, other.y + other.height) - y,
+        }
+    }
+}
+
+/// DirtyGrid wraps a 2D grid of cells of type T, accumulating dirty rects as
+/// cells are written.
+/// Rects which overlap or touch are merged into their bounding rect,
+/// so the rects to redraw never overlap.
+pub struct DirtyGrid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+    rects: Vec<Rect>,
+}
+
+impl<T> DirtyGrid<T> {
+    /// Create a new DirtyGrid from cells in row major order, every cell starts
+    /// dirty.
+    pub fn from_vec(width: usize, height: usize, cells: Vec<T>) -> DirtyGrid<T> {
+        assert!(cells.len() == width * height, "cells must hold width * height values");
+        DirtyGrid {
+            cells,
+            width,
+            height,
+            rects: vec![Rect::new(0, 0, width, height)],
+        }
+    }
+
+    /// Returns the number of columns.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns true if any cell is dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        !self.rects.is_empty()
+    }
+
+    /// Read the cell at column x, row y.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        match x < self.width && y < self.height {
+            true => Some(&self.cells[y * self.width + x]),
+            false => None,
+        }
+    }
+
+    /// Writable cell return at column x, row y, marks the cell dirty.
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.mark(Rect::new(x, y, 1, 1));
+        Some(&mut self.cells[y * self.width + x])
+    }
+
+    /// Set the cell at column x, row y, marks the cell dirty.
+    pub fn set(&mut self, x: usize, y: usize, val: T) {
+        *self.get_mut(x, y).expect("cell out of bounds") = val;
+    }
+
+    /// Marks the cells within rect dirty, clipped to the grid.
+    pub fn mark(&mut self, rect: Rect) {
+        if rect.x >= self.width || rect.y >= self.height {
+            return;
+        }
+        let mut rect = Rect {
+            width: cmp::min(rect.width, self.width - rect.x),
+            height: cmp::min(rect.height, self.height - rect.y),
+            ..rect
+        };
+        if rect.width == 0 || rect.height == 0 {
+            return;
+        }
+        // Merging may grow the rect into others, repeat until none touch it.
+        while let Some(i) = self.rects.iter().position(|r| r.touches(&rect)) {
+            rect = rect.union(&self.rects.swap_remove(i));
+        }
+        self.rects.push(rect);
+    }
+
+    /// Returns the dirty rects.
+    pub fn rects(&self) -> &[Rect] {
+        &self.rects
+    }
+
+    /// Returns the dirty rects, clearing them.
+    pub fn take_rects(&mut self) -> Vec<Rect> {
+        mem::take(&mut self.rects)
+    }
+
+    /// Clears the dirty rects.
+    pub fn clear(&mut self) {
+        self.rects.clear();
+    }
+
+    /// Consumes the DirtyGrid, returning the cells in row major order.
+    pub fn into_inner(self) -> Vec<T> {
+        self.cells
+    }
+}
+
+impl<T> DirtyGrid<T> where T: Clone {
+    /// Create a new DirtyGrid with every cell set to val, every cell starts
+    /// dirty.
+    pub fn new(width: usize, height: usize, val: T) -> DirtyGrid<T> {
+        DirtyGrid::from_vec(width, height, vec![val; width * height])
+    }
+
+    /// Set every cell within rect, clipped to the grid, marks them dirty.
+    pub fn fill_rect(&mut self, rect: Rect, val: T) {
+        let right = cmp::min(rect.x + rect.width, self.width);
+        let bottom = cmp::min(rect.y + rect.height, self.height);
+        for y in rect.y..bottom {
+            for x in rect.x..right {
+                self.cells[y * self.width + x] = val.clone();
+            }
+        }
+        self.mark(rect);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirtyGrid, Rect};
+
+    fn clean() -> DirtyGrid<u8> {
+        let mut grid = DirtyGrid::new(10, 10, 0);
+        grid.clear();
+        grid
+    }
+
+    #[test]
+    fn new_all_dirty() {
+        let mut grid = DirtyGrid::new(4, 3, 0u8);
+        assert!(grid.take_rects() == vec![Rect::new(0, 0, 4, 3)]);
+        assert!(!grid.dirty());
+    }
+
+    #[test]
+    fn separate_cells_separate_rects() {
+        let mut grid = clean();
+        grid.set(0, 0, 1);
+        grid.set(5, 5, 2);
+        assert!(grid.rects() == [Rect::new(0, 0, 1, 1), Rect::new(5, 5, 1, 1)]);
+        assert!(grid.get(5, 5) == Some(&2));
+        assert!(grid.get(10, 0).is_none());
+    }
+
+    #[test]
+    fn touching_rects_merge() {
+        let mut grid = clean();
+        grid.set(1, 1, 1);
+        grid.set(2, 1, 1);
+        assert!(grid.rects() == [Rect::new(1, 1, 2, 1)]);
+        grid.set(5, 1, 1);
+        grid.fill_rect(Rect::new(2, 0, 3, 3), 2);
+        assert!(grid.take_rects() == vec![Rect::new(1, 0, 5, 3)]);
+    }
+
+    #[test]
+    fn mark_clips_to_grid() {
+        let mut grid = clean();
+        grid.mark(Rect::new(8, 8, 5, 5));
+        grid.mark(Rect::new(20, 0, 1, 1));
+        assert!(grid.rects() == [Rect::new(8, 8, 2, 2)]);
+        assert!(grid.get_mut(10, 10).is_none());
+    }
+}
@@ -0,0 +1,112 @@
+use alloc::vec::Vec;
+
+use track::Tracked;
+
+/// DirtyGroup borrows any number of Tracked values, so they may be checked
+/// and cleared together.
+///
+/// A group is cheap to build, typically for the duration of a frame.
+///
+/// ```
+/// use dirty::{Dirty, DirtyGroup};
+///
+/// let mut position = Dirty::new((0, 0));
+/// let mut name = Dirty::new("a");
+/// let mut group = DirtyGroup::new();
+/// group.register(&mut position).register(&mut name);
+/// assert!(group.dirty_count() == 2);
+/// group.clear_all();
+/// assert!(!group.any_dirty());
+/// ```
+pub struct DirtyGroup<'a> {
+    members: Vec<&'a mut dyn Tracked>,
+}
+
+impl<'a> DirtyGroup<'a> {
+    /// Create a new empty DirtyGroup.
+    pub fn new() -> DirtyGroup<'a> {
+        DirtyGroup {
+            members: Vec::new(),
+        }
+    }
+
+    /// Adds a value to the group.
+    pub fn register(&mut self, value: &'a mut dyn Tracked) -> &mut Self {
+        self.members.push(value);
+        self
+    }
+
+    /// Returns the number of values in the group.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns true if the group has no values, false otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Returns true if any value is dirty, false otherwise.
+    pub fn any_dirty(&self) -> bool {
+        self.members.iter().any(|m| m.dirty())
+    }
+
+    /// Returns the number of dirty values.
+    pub fn dirty_count(&self) -> usize {
+        self.members.iter().filter(|m| m.dirty()).count()
+    }
+
+    /// Clears the dirty flag of every value.
+    pub fn clear_all(&mut self) {
+        for m in self.members.iter_mut() {
+            m.clear();
+        }
+    }
+}
+
+impl<'a> Default for DirtyGroup<'a> {
+    fn default() -> Self {
+        DirtyGroup::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtyGroup;
+    use {Dirty, DirtyCell, DirtyVec};
+
+    #[test]
+    fn any_dirty_and_count() {
+        let mut a = Dirty::new(0);
+        let mut b = DirtyCell::new(0);
+        let mut c: DirtyVec<i32> = DirtyVec::new();
+        a.clear();
+        b.clear();
+        {
+            let mut group = DirtyGroup::new();
+            group.register(&mut a).register(&mut b).register(&mut c);
+            assert!(group.len() == 3);
+            assert!(!group.any_dirty());
+            assert!(group.dirty_count() == 0);
+        }
+        *a.write() += 1;
+        c.push(1);
+        let mut group = DirtyGroup::new();
+        group.register(&mut a).register(&mut b).register(&mut c);
+        assert!(group.any_dirty());
+        assert!(group.dirty_count() == 2);
+    }
+
+    #[test]
+    fn clear_all() {
+        let mut a = Dirty::new(0);
+        let mut b = Dirty::new(1);
+        {
+            let mut group = DirtyGroup::new();
+            group.register(&mut a).register(&mut b);
+            group.clear_all();
+        }
+        assert!(!a.dirty());
+        assert!(!b.dirty());
+    }
+}
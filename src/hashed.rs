@@ -0,0 +1,144 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::ops::Deref;
+
+/// HashDirty wraps a value of type T and detects changes by hashing it,
+/// recording a hash at clear() and reporting dirty when a rehash on check()
+/// differs. Mutations made where no wrapper can see them, such as through FFI
+/// or interior mutability, are caught and writes leaving the value as it was
+/// are not reported.
+/// A new HashDirty starts dirty.
+pub struct HashDirty<T, S = RandomState> where T: Hash, S: BuildHasher {
+    value: T,
+    hash: Option<u64>,
+    hasher: S,
+}
+
+impl<T> HashDirty<T> where T: Hash {
+    /// Create a new HashDirty.
+    pub fn new(val: T) -> HashDirty<T> {
+        HashDirty::with_hasher(val, RandomState::new())
+    }
+
+    /// Create a new clean HashDirty.
+    pub fn new_clean(val: T) -> HashDirty<T> {
+        let mut hashed = HashDirty::new(val);
+        hashed.clear();
+        hashed
+    }
+}
+
+impl<T, S> HashDirty<T, S> where T: Hash, S: BuildHasher {
+    /// Create a new HashDirty hashing with the given hasher.
+    pub fn with_hasher(val: T, hasher: S) -> HashDirty<T, S> {
+        HashDirty {
+            value: val,
+            hash: None,
+            hasher,
+        }
+    }
+
+    /// Returns true if the value hashes differently than at the last clear(),
+    /// false otherwise. Rehashes the value on every call.
+    pub fn check(&self) -> bool {
+        self.hash != Some(self.hasher.hash_one(&self.value))
+    }
+
+    /// Writable value return, changes are seen by the next check().
+    pub fn write(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Set the value.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Clears the dirty state, recording the hash of the value.
+    pub fn clear(&mut self) {
+        self.hash = Some(self.hasher.hash_one(&self.value));
+    }
+
+    /// Read the value only if check() reports a change, clearing the dirty
+    /// state.
+    pub fn take_dirty(&mut self) -> Option<&T> {
+        let hash = self.hasher.hash_one(&self.value);
+        match self.hash != Some(hash) {
+            true => {
+                self.hash = Some(hash);
+                Some(&self.value)
+            },
+            false => None,
+        }
+    }
+
+    /// Consumes the HashDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, S> Deref for HashDirty<T, S> where T: Hash, S: BuildHasher {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Default for HashDirty<T> where T: Hash + Default {
+    fn default() -> Self {
+        HashDirty::new(T::default())
+    }
+}
+
+impl<T, S> fmt::Debug for HashDirty<T, S> where T: Hash + fmt::Debug, S: BuildHasher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HashDirty")
+            .field("value", &self.value)
+            .field("hash", &self.hash)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HashDirty;
+    use std::cell::Cell;
+    use std::hash::{Hash, Hasher};
+    use std::vec::Vec;
+
+    #[test]
+    fn identical_writes_are_clean() {
+        let mut dirty = HashDirty::new(vec![1, 2, 3]);
+        assert!(dirty.check());
+        dirty.clear();
+        dirty.write()[0] = 1;
+        assert!(!dirty.check());
+        dirty.write().push(4);
+        assert!(dirty.check());
+        assert!(dirty.take_dirty() == Some(&vec![1, 2, 3, 4]));
+        assert!(dirty.take_dirty().is_none());
+    }
+
+    struct Shared(Cell<u32>);
+
+    impl Hash for Shared {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.0.get().hash(state)
+        }
+    }
+
+    #[test]
+    fn sees_interior_mutation() {
+        let dirty = HashDirty::new_clean(Shared(Cell::new(1)));
+        dirty.0.set(2);
+        assert!(dirty.check());
+        assert!(HashDirty::new_clean(Vec::<u8>::new()).read().is_empty());
+    }
+}
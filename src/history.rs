@@ -0,0 +1,192 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+
+/// HistoryDirty wraps a value of type T like Dirty and keeps up to limit
+/// snapshots of the value, taken on clear() or checkpoint(), which undo() and
+/// redo() step through.
+/// Writing after an undo() discards the snapshots redo() would restore.
+pub struct HistoryDirty<T> where T: Clone {
+    value: T,
+    dirty: bool,
+    // True if the value was written since the last snapshot, otherwise the
+    // value equals the newest snapshot.
+    changed: bool,
+    undo: VecDeque<T>,
+    redo: Vec<T>,
+    limit: usize,
+}
+
+impl<T> HistoryDirty<T> where T: Clone {
+    /// Create a new dirty HistoryDirty keeping up to limit snapshots, the
+    /// first of which is val.
+    /// Panics if limit is 0.
+    pub fn new(val: T, limit: usize) -> HistoryDirty<T> {
+        assert!(limit > 0, "HistoryDirty limit must be at least 1");
+        let mut undo = VecDeque::with_capacity(limit);
+        undo.push_back(val.clone());
+        HistoryDirty {
+            value: val,
+            dirty: true,
+            changed: false,
+            undo,
+            redo: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.modify();
+        &mut self.value
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.modify();
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Takes a snapshot of the value if written since the last snapshot,
+    /// dropping the oldest snapshot past the limit.
+    pub fn checkpoint(&mut self) {
+        if self.changed {
+            if self.undo.len() == self.limit {
+                self.undo.pop_front();
+            }
+            self.undo.push_back(self.value.clone());
+            self.changed = false;
+        }
+    }
+
+    /// Clears the dirty flag, taking a snapshot of the value.
+    pub fn clear(&mut self) {
+        self.checkpoint();
+        self.dirty = false;
+    }
+
+    /// Returns true if undo() would restore a snapshot, false otherwise.
+    pub fn can_undo(&self) -> bool {
+        match self.changed {
+            true => !self.undo.is_empty(),
+            false => self.undo.len() > 1,
+        }
+    }
+
+    /// Returns true if redo() would restore a snapshot, false otherwise.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Restores the newest snapshot older than the value, setting the dirty
+    /// flag. Returns false if there is none.
+    pub fn undo(&mut self) -> bool {
+        if !self.can_undo() {
+            return false;
+        }
+        match self.changed {
+            true => self.redo.push(self.value.clone()),
+            // The newest snapshot is the value itself.
+            false => self.redo.extend(self.undo.pop_back()),
+        }
+        if let Some(snapshot) = self.undo.back() {
+            self.value.clone_from(snapshot);
+        }
+        self.changed = false;
+        self.dirty = true;
+        true
+    }
+
+    /// Restores the snapshot last undone, setting the dirty flag. Returns
+    /// false if there is none.
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(snapshot) => {
+                self.value.clone_from(&snapshot);
+                if self.undo.len() == self.limit {
+                    self.undo.pop_front();
+                }
+                self.undo.push_back(snapshot);
+                self.dirty = true;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Consumes the HistoryDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    fn modify(&mut self) {
+        self.dirty = true;
+        self.changed = true;
+        self.redo.clear();
+    }
+}
+
+impl<T> Deref for HistoryDirty<T> where T: Clone {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> fmt::Debug for HistoryDirty<T> where T: Clone + fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HistoryDirty")
+            .field("value", &self.value)
+            .field("dirty", &self.dirty)
+            .field("undo", &self.undo)
+            .field("redo", &self.redo)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HistoryDirty;
+
+    #[test]
+    fn undo_redo() {
+        let mut doc = HistoryDirty::new(0, 8);
+        doc.clear();
+        assert!(!doc.can_undo());
+        doc.set(1);
+        doc.clear();
+        doc.set(2);
+        // Unsaved edits are undone first, then each clear.
+        assert!(doc.undo() && *doc == 1 && doc.dirty());
+        assert!(doc.undo() && *doc == 0);
+        assert!(!doc.undo());
+        assert!(doc.redo() && *doc == 1);
+        assert!(doc.redo() && *doc == 2);
+        assert!(!doc.redo());
+        assert!(doc.undo() && *doc == 1);
+        *doc.write() += 10;
+        assert!(!doc.can_redo());
+    }
+
+    #[test]
+    fn limit_drops_oldest() {
+        let mut doc = HistoryDirty::new(0, 2);
+        for i in 1..5 {
+            doc.set(i);
+            doc.checkpoint();
+        }
+        assert!(doc.undo() && *doc == 3);
+        assert!(!doc.undo());
+    }
+}
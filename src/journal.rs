@@ -0,0 +1,126 @@
+use alloc::vec::{self, Vec};
+use core::fmt;
+use core::ops::Deref;
+
+/// Apply is implemented by the operations of a JournaledDirty, mutating the
+/// journaled value.
+pub trait Apply<T> {
+    /// Applies the operation to target.
+    fn apply(&self, target: &mut T);
+}
+
+/// JournaledDirty wraps a value of type T which is only written by applying
+/// operations of type Op, recording each applied operation in a journal so
+/// consumers may replay the changes since the last clear elsewhere.
+/// The value is dirty while the journal is not empty.
+pub struct JournaledDirty<T, Op> {
+    value: T,
+    journal: Vec<Op>,
+}
+
+impl<T, Op> JournaledDirty<T, Op> where Op: Apply<T> {
+    /// Create a new JournaledDirty with an empty journal.
+    pub fn new(val: T) -> JournaledDirty<T, Op> {
+        JournaledDirty {
+            value: val,
+            journal: Vec::new(),
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        !self.journal.is_empty()
+    }
+
+    /// Applies op to the value and appends it to the journal.
+    pub fn apply(&mut self, op: Op) {
+        op.apply(&mut self.value);
+        self.journal.push(op);
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the operations applied since the last clear, oldest first.
+    pub fn ops(&self) -> &[Op] {
+        &self.journal
+    }
+
+    /// Removes and returns the operations applied since the last clear,
+    /// oldest first, clearing the dirty flag.
+    pub fn drain_ops(&mut self) -> vec::Drain<'_, Op> {
+        self.journal.drain(..)
+    }
+
+    /// Clears the dirty flag, dropping the journal.
+    pub fn clear(&mut self) {
+        self.journal.clear();
+    }
+
+    /// Consumes the JournaledDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, Op> Deref for JournaledDirty<T, Op> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, Op> Default for JournaledDirty<T, Op> where T: Default, Op: Apply<T> {
+    fn default() -> Self {
+        JournaledDirty::new(T::default())
+    }
+}
+
+impl<T, Op> fmt::Debug for JournaledDirty<T, Op> where T: fmt::Debug, Op: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("JournaledDirty")
+            .field("value", &self.value)
+            .field("journal", &self.journal)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Apply, JournaledDirty};
+    use alloc::vec::Vec;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Op {
+        Add(i32),
+        Mul(i32),
+    }
+
+    impl Apply<i32> for Op {
+        fn apply(&self, target: &mut i32) {
+            match *self {
+                Op::Add(n) => *target += n,
+                Op::Mul(n) => *target *= n,
+            }
+        }
+    }
+
+    #[test]
+    fn journal_replays() {
+        let mut dirty = JournaledDirty::new(1);
+        assert!(!dirty.dirty());
+        dirty.apply(Op::Add(2));
+        dirty.apply(Op::Mul(4));
+        assert!(dirty.dirty() && *dirty == 12);
+        let ops: Vec<Op> = dirty.drain_ops().collect();
+        assert!(ops == [Op::Add(2), Op::Mul(4)]);
+        assert!(!dirty.dirty());
+        let mut replica = 1;
+        for op in ops.iter() {
+            op.apply(&mut replica);
+        }
+        assert!(replica == *dirty);
+    }
+}
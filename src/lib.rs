@@ -1,70 +1,833 @@
-use std::ops::Deref;
+#![no_std]
+
+#[cfg(feature = "alloc")]
+#[macro_use]
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+#[cfg(feature = "bytemuck")]
+extern crate bytemuck;
+#[cfg(feature = "crc32")]
+extern crate crc32fast;
+#[cfg(feature = "derive")]
+extern crate dirty_derive;
+#[cfg(feature = "futures")]
+extern crate futures_core;
+#[cfg(feature = "notify")]
+extern crate notify;
+#[cfg(feature = "prost")]
+extern crate prost_types;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(any(feature = "json", all(test, feature = "serde")))]
+extern crate serde_json;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+
+use core::borrow::{Borrow, BorrowMut};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::mem;
+use core::ops::{Deref, DerefMut, Index, IndexMut};
+
+mod all;
+mod array;
+#[cfg(feature = "std")]
+mod async_dirty;
+mod atomic;
+mod baseline;
+mod bits;
+#[cfg(feature = "alloc")]
+mod boxed;
+#[cfg(feature = "alloc")]
+mod btree_map;
+#[cfg(feature = "alloc")]
+mod buffer;
+#[cfg(feature = "std")]
+mod cache;
+mod cell;
+#[cfg(feature = "alloc")]
+mod change;
+mod checksum;
+mod counted;
+#[cfg(feature = "alloc")]
+mod cow;
+mod delta;
+#[cfg(target_has_atomic = "64")]
+mod epoch;
+mod fields;
+#[cfg(feature = "json")]
+mod file;
+mod flag;
+#[cfg(feature = "std")]
+mod flush;
+#[cfg(feature = "alloc")]
+pub mod graph;
+#[cfg(feature = "alloc")]
+mod grid;
+#[cfg(feature = "alloc")]
+mod group;
+#[cfg(feature = "std")]
+mod hashed;
+#[cfg(feature = "alloc")]
+mod history;
+#[cfg(feature = "alloc")]
+mod journal;
+#[cfg(feature = "std")]
+mod map;
+mod memo;
+mod must;
+mod observe;
+#[cfg(feature = "ops")]
+mod ops;
+#[cfg(feature = "alloc")]
+mod paged;
+#[cfg(feature = "json")]
+mod patch;
+mod pin;
+#[cfg(feature = "bytemuck")]
+mod pod;
+#[cfg(feature = "alloc")]
+mod ranges;
+mod redraw;
+#[cfg(feature = "std")]
+mod set;
+#[cfg(feature = "alloc")]
+mod slab;
+#[cfg(feature = "alloc")]
+mod slice;
+#[cfg(feature = "sql")]
+mod sql;
+mod snapshot;
+#[cfg(feature = "std")]
+mod split;
+mod stat;
+#[cfg(feature = "std")]
+mod store;
+#[cfg(feature = "alloc")]
+mod string;
+mod swap;
+#[cfg(feature = "std")]
+pub mod sync;
+mod tagged;
+mod tick;
+#[cfg(feature = "std")]
+mod timed;
+mod track;
+mod transaction;
+#[cfg(feature = "alloc")]
+mod tree;
+#[cfg(feature = "std")]
+mod triple;
+#[cfg(feature = "serde")]
+pub mod serde_helpers;
+mod validated;
+#[cfg(feature = "alloc")]
+mod vec;
+mod versioned;
+mod view;
+#[cfg(feature = "tokio")]
+mod watch;
+#[cfg(feature = "notify")]
+mod watcher;
+
+pub use all::{ClearAll, DirtyAll};
+pub use array::DirtyArray;
+#[cfg(feature = "std")]
+pub use async_dirty::{AsyncDirty, WaitDirty};
+#[cfg(feature = "futures")]
+pub use async_dirty::ChangeStream;
+pub use atomic::AtomicDirty;
+pub use baseline::BaselineDirty;
+pub use bits::{DirtyBits, SetIndices};
+#[cfg(feature = "alloc")]
+pub use boxed::DirtyBox;
+#[cfg(feature = "alloc")]
+pub use btree_map::DirtyBTreeMap;
+#[cfg(feature = "alloc")]
+pub use buffer::DirtyBuffer;
+#[cfg(feature = "std")]
+pub use cache::DirtyCache;
+pub use cell::{DirtyCell, DirtyRefCell};
+#[cfg(feature = "alloc")]
+pub use change::KeyChange;
+#[cfg(feature = "crc32")]
+pub use checksum::Crc32;
+pub use checksum::{Checksum, ChecksumDirty, Fnv1a};
+pub use counted::CountedDirty;
+pub use delta::DeltaDirty;
+#[cfg(target_has_atomic = "64")]
+pub use epoch::{Epoch, EpochDirty};
+pub use fields::{DirtyFields, StructFields};
+#[cfg(feature = "json")]
+pub use file::FileDirty;
+pub use flag::{DirtyFlag, SharedFlag};
+#[cfg(feature = "std")]
+pub use flush::FlushTo;
+#[cfg(feature = "alloc")]
+pub use grid::{DirtyGrid, Rect};
+#[cfg(feature = "alloc")]
+pub use group::DirtyGroup;
+#[cfg(feature = "std")]
+pub use hashed::HashDirty;
+#[cfg(feature = "alloc")]
+pub use history::HistoryDirty;
+#[cfg(feature = "alloc")]
+pub use journal::{Apply, JournaledDirty};
+#[cfg(feature = "std")]
+pub use map::DirtyHashMap;
+pub use memo::Memo;
+pub use must::MustFlush;
+pub use observe::{ObservedDirty, ObservedWriteGuard};
+#[cfg(feature = "alloc")]
+pub use paged::PagedDirty;
+#[cfg(feature = "json-patch")]
+pub use patch::JsonPatch;
+#[cfg(feature = "json")]
+pub use patch::MergePatch;
+pub use redraw::RedrawTracker;
+#[cfg(feature = "std")]
+pub use set::DirtySet;
+#[cfg(feature = "alloc")]
+pub use slab::{DirtySlab, SlabKey};
+#[cfg(feature = "alloc")]
+pub use slice::DirtySlice;
+#[cfg(feature = "sql")]
+pub use sql::SqlUpdate;
+pub use snapshot::SnapshotDirty;
+#[cfg(feature = "std")]
+pub use split::{split, ReadHandle, WriteHandle};
+pub use stat::{StatDirty, Stats};
+#[cfg(feature = "std")]
+pub use store::{Backend, DirtyStore, FsBackend, MemoryBackend};
+#[cfg(feature = "alloc")]
+pub use string::DirtyString;
+pub use swap::DirtySwap;
+pub use tagged::{Aspects, TaggedDirty};
+pub use tick::TickDirty;
+#[cfg(feature = "std")]
+pub use timed::TimedDirty;
+pub use track::Tracked;
+pub use transaction::Transaction;
+#[cfg(feature = "alloc")]
+pub use tree::{DirtyNode, DirtyTree, NodeId};
+#[cfg(feature = "std")]
+pub use triple::{TripleBufferDirty, TripleBufferReader};
+pub use validated::ValidatedDirty;
+#[cfg(feature = "alloc")]
+pub use vec::{DirtyVec, SpliceOp};
+pub use versioned::{ReaderToken, VersionedDirty};
+pub use view::{zip, DirtyView, Zip};
+#[cfg(feature = "tokio")]
+pub use watch::WatchDirty;
+#[cfg(feature = "notify")]
+pub use watcher::WatchedDirty;
+#[cfg(feature = "derive")]
+pub use dirty_derive::DirtyFields;
 
 /// Dirty wraps a value of type T with functions similiar to that of a Read/Write
 /// lock but simply sets a dirty flag on write(), reset on clear().
-/// Use read() or deref (*dirty_variable) to access the inner value.
-pub struct Dirty<T> {
+/// Use read() or deref (*dirty_variable) to access the inner value, a mutable
+/// deref (*dirty_variable = x) behaves like write() and sets the dirty flag.
+/// The flag is stored as a D, a bool by default, see DirtyFlag.
+pub struct Dirty<T, D = bool> where T: ?Sized {
+    flag: D,
+    poisoned: bool,
+    #[cfg(feature = "tracing")]
+    label: Option<&'static str>,
     value: T,
-    dirty: bool,
 }
 
 impl<T> Dirty<T> {
     /// Create a new Dirty.
-    pub fn new(val: T) -> Dirty<T> {
+    pub const fn new(val: T) -> Dirty<T> {
+        Dirty::from_parts(val, true)
+    }
+
+    /// Create a new clean Dirty.
+    pub const fn new_clean(val: T) -> Dirty<T> {
+        Dirty::from_parts(val, false)
+    }
+
+    /// Create a new Dirty from a value and dirty flag, the inverse of
+    /// into_parts().
+    pub const fn from_parts(val: T, dirty: bool) -> Dirty<T> {
+        Dirty::from_flag(val, dirty)
+    }
+}
+
+impl<T, D> Dirty<T, D> {
+    /// Create a new Dirty with the given flag storage.
+    pub const fn from_flag(val: T, flag: D) -> Dirty<T, D> {
         Dirty {
             value: val,
-            dirty: true,
+            flag,
+            poisoned: false,
+            #[cfg(feature = "tracing")]
+            label: None,
         }
     }
+}
 
+impl<T, D> Dirty<T, D> where D: DirtyFlag {
+    /// Sets a label identifying the value in trace events.
+    #[cfg(feature = "tracing")]
+    pub fn with_label(mut self, label: &'static str) -> Dirty<T, D> {
+        self.label = Some(label);
+        self
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.mark();
+    }
+
+    /// Replace the value, returning the old value, sets the dirty flag.
+    pub fn replace(&mut self, val: T) -> T {
+        self.mark();
+        mem::replace(&mut self.value, val)
+    }
+
+    /// Swap the values of two Dirty, sets the dirty flag of both as both
+    /// values changed. The flags themselves are not swapped.
+    pub fn swap(&mut self, other: &mut Dirty<T, D>) {
+        mem::swap(&mut self.value, &mut other.value);
+        self.mark();
+        other.mark();
+    }
+
+    /// Swap the value with other, sets the dirty flag.
+    pub fn swap_value(&mut self, other: &mut T) {
+        mem::swap(&mut self.value, other);
+        self.mark();
+    }
+
+    /// Maps the value with f, keeping the dirty flag.
+    pub fn map<U, F>(self, f: F) -> Dirty<U, D> where F: FnOnce(T) -> U {
+        Dirty {
+            value: f(self.value),
+            flag: self.flag,
+            poisoned: self.poisoned,
+            #[cfg(feature = "tracing")]
+            label: self.label,
+        }
+    }
+
+    /// Consumes the Dirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Consumes the Dirty, returning the inner value and the dirty flag.
+    pub fn into_parts(self) -> (T, bool) {
+        let dirty = self.flag.get();
+        (self.value, dirty)
+    }
+}
+
+impl<T, D> Dirty<T, D> where T: ?Sized, D: DirtyFlag {
     /// Returns true if dirty, false otherwise.
     pub fn dirty(&self) -> bool {
-        self.dirty
+        self.flag.get()
+    }
+
+    /// Returns true if clean, false otherwise.
+    pub fn is_clean(&self) -> bool {
+        !self.flag.get()
+    }
+
+    /// Returns true if a write guard or a closure given to update(),
+    /// update_and() or try_update() panicked, so the value may be half
+    /// written, false otherwise. Check before flushing.
+    /// Panics are only detected with the std feature, without it a Dirty is
+    /// never poisoned.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Clears the poisoned state.
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+
+    /// Returns the label set with with_label(), if any.
+    #[cfg(feature = "tracing")]
+    pub fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    /// Sets the dirty flag, tracing the clean to dirty transition.
+    fn mark(&mut self) {
+        #[cfg(feature = "tracing")]
+        {
+            if !self.flag.get() {
+                self.trace("dirtied");
+            }
+        }
+        self.flag.mark();
+    }
+
+    #[cfg(feature = "tracing")]
+    fn trace(&self, event: &'static str) {
+        tracing::trace!(label = self.label, value = core::any::type_name::<T>(), "{}", event);
     }
 
     /// Writable value return, sets the dirty flag.
     pub fn write(&mut self) -> &mut T {
-        self.dirty = true;
+        self.mark();
+        &mut self.value
+    }
+
+    /// Writable value return which does not set the dirty flag, for changes
+    /// consumers need not see such as cached state within the value.
+    pub fn write_silent(&mut self) -> &mut T {
         &mut self.value
     }
 
+    /// Writable guard return, sets the dirty flag when the guard is dropped
+    /// only if it was mutably dereferenced.
+    pub fn write_guard(&mut self) -> DirtyWriteGuard<'_, T, D> {
+        DirtyWriteGuard {
+            dirty: self,
+            modified: false,
+        }
+    }
+
+    /// Run f with the writable value, sets the dirty flag.
+    pub fn update<F>(&mut self, f: F) -> &mut Self where F: FnOnce(&mut T) {
+        self.update_and(f);
+        self
+    }
+
+    /// Run f with the writable value returning its result, sets the dirty
+    /// flag.
+    pub fn update_and<R, F>(&mut self, f: F) -> R where F: FnOnce(&mut T) -> R {
+        self.mark();
+        #[cfg(feature = "std")]
+        let _poison = PoisonOnPanic(&mut self.poisoned);
+        f(&mut self.value)
+    }
+
+    /// Run f with the writable value, sets the dirty flag only if it returns
+    /// Ok. On Err only the flag is left as it was, the value is not rolled
+    /// back, so f should not write before it knows it will succeed. A write
+    /// made before an Err is kept without setting the flag and is missed by
+    /// the next flush unless something else dirties the value.
+    /// SnapshotDirty::try_update() also restores the value.
+    pub fn try_update<E, F>(&mut self, f: F) -> Result<(), E>
+        where F: FnOnce(&mut T) -> Result<(), E>
+    {
+        {
+            #[cfg(feature = "std")]
+            let _poison = PoisonOnPanic(&mut self.poisoned);
+            f(&mut self.value)?;
+        }
+        self.mark();
+        Ok(())
+    }
+
     /// Read the value.
     pub fn read(&self) -> &T {
         &self.value
     }
-    
+
     /// Clears the dirty flag.
     pub fn clear(&mut self) {
-        self.dirty = false;
+        #[cfg(feature = "tracing")]
+        {
+            if self.flag.get() {
+                self.trace("cleared");
+            }
+        }
+        self.flag.clear();
+    }
+
+    /// If dirty, clears the dirty flag only if pred returns true for the
+    /// value. Returns true if cleared, false otherwise.
+    pub fn clear_if<F>(&mut self, pred: F) -> bool where F: FnOnce(&T) -> bool {
+        if self.flag.get() && pred(&self.value) {
+            self.clear();
+            return true;
+        }
+        false
     }
 
     /// Read the value only if modified since last read.
     pub fn read_dirty(&self) -> Option<&T> {
-        match self.dirty {
+        match self.flag.get() {
+            true => {
+                Some(&self.value)
+            },
+            false => None,
+        }
+    }
+
+    /// Read the value only if modified since last read, clearing the dirty
+    /// flag.
+    pub fn take_dirty(&mut self) -> Option<&T> {
+        match self.flag.get() {
             true => {
+                self.clear();
                 Some(&self.value)
             },
             false => None,
         }
     }
+
+    /// If dirty, run f with the value and clear the dirty flag only if it
+    /// returns Ok. Returns true if f ran and succeeded, false if clean.
+    pub fn flush<E, F>(&mut self, f: F) -> Result<bool, E>
+        where F: FnOnce(&T) -> Result<(), E>
+    {
+        if !self.flag.get() {
+            return Ok(false);
+        }
+        f(&self.value)?;
+        #[cfg(feature = "tracing")]
+        self.trace("flushed");
+        self.flag.clear();
+        Ok(true)
+    }
+
+    /// If dirty, run f with the value and clear the dirty flag, returning the
+    /// result of f. Returns None without running f if clean.
+    pub fn render_if_dirty<R, F>(&mut self, f: F) -> Option<R> where F: FnOnce(&T) -> R {
+        match self.flag.get() {
+            true => {
+                self.clear();
+                Some(f(&self.value))
+            },
+            false => None,
+        }
+    }
+
+    /// Borrows the value as a Dirty<&T> with the same dirty flag.
+    pub fn as_dirty_ref(&self) -> Dirty<&T> {
+        Dirty {
+            value: &self.value,
+            flag: self.flag.get(),
+            poisoned: self.poisoned,
+            #[cfg(feature = "tracing")]
+            label: self.label,
+        }
+    }
+
+    /// Mutably borrows the value as a Dirty<&mut T> with the same dirty flag.
+    /// Like write() this sets the dirty flag, as writes through the borrow
+    /// cannot be seen.
+    pub fn as_dirty_mut(&mut self) -> Dirty<&mut T> {
+        let dirty = self.flag.get();
+        self.mark();
+        Dirty {
+            value: &mut self.value,
+            flag: dirty,
+            poisoned: self.poisoned,
+            #[cfg(feature = "tracing")]
+            label: self.label,
+        }
+    }
+}
+
+impl<T, D> Dirty<T, D> where T: ?Sized, D: SharedFlag {
+    /// Sets the dirty flag through a shared reference, for invalidating a
+    /// value without writing it.
+    pub fn mark_shared(&self) {
+        self.flag.mark_shared();
+    }
+
+    /// Clears the dirty flag through a shared reference.
+    pub fn clear_shared(&self) {
+        self.flag.take();
+    }
+
+    /// Read the value only if modified since last read, clearing the dirty
+    /// flag through a shared reference.
+    pub fn take_dirty_shared(&self) -> Option<&T> {
+        match self.flag.take() {
+            true => Some(&self.value),
+            false => None,
+        }
+    }
 }
 
-impl<T> Deref for Dirty<T> {
+impl<T> Dirty<T, u64> where T: ?Sized {
+    /// Returns the version, incremented by every write whether or not the
+    /// value was already dirty.
+    pub fn version(&self) -> u64 {
+        self.flag >> 1
+    }
+}
+
+impl<T, D> Deref for Dirty<T, D> where T: ?Sized {
     type Target = T;
     fn deref(&self) -> &T {
         &self.value
     }
 }
 
-impl<T> Default for Dirty<T> where T: Default {
+impl<T, D> DerefMut for Dirty<T, D> where T: ?Sized, D: DirtyFlag {
+    fn deref_mut(&mut self) -> &mut T {
+        self.write()
+    }
+}
+
+impl<T, D, I> Index<I> for Dirty<T, D> where T: ?Sized + Index<I> {
+    type Output = T::Output;
+    fn index(&self, index: I) -> &T::Output {
+        &self.value[index]
+    }
+}
+
+/// Like write() this sets the dirty flag.
+impl<T, D, I> IndexMut<I> for Dirty<T, D> where T: ?Sized + IndexMut<I>, D: DirtyFlag {
+    fn index_mut(&mut self, index: I) -> &mut T::Output {
+        &mut self.write()[index]
+    }
+}
+
+impl<T, D, U> AsRef<U> for Dirty<T, D> where T: ?Sized + AsRef<U>, U: ?Sized {
+    fn as_ref(&self) -> &U {
+        self.value.as_ref()
+    }
+}
+
+/// Like write() this sets the dirty flag.
+impl<T, D, U> AsMut<U> for Dirty<T, D> where T: ?Sized + AsMut<U>, D: DirtyFlag, U: ?Sized {
+    fn as_mut(&mut self) -> &mut U {
+        self.write().as_mut()
+    }
+}
+
+impl<T, D> Borrow<T> for Dirty<T, D> where T: ?Sized {
+    fn borrow(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Like write() this sets the dirty flag.
+impl<T, D> BorrowMut<T> for Dirty<T, D> where T: ?Sized, D: DirtyFlag {
+    fn borrow_mut(&mut self) -> &mut T {
+        self.write()
+    }
+}
+
+impl<T, D> Dirty<T, D> where T: Clone, D: DirtyFlag {
+    /// Clone the value only if modified since last read, clearing the dirty
+    /// flag.
+    pub fn take_dirty_cloned(&mut self) -> Option<T> {
+        self.take_dirty().cloned()
+    }
+}
+
+impl<T, D> Dirty<T, D> where T: Default, D: DirtyFlag {
+    /// Take the value, leaving the default in its place, sets the dirty flag.
+    pub fn take(&mut self) -> T {
+        self.replace(T::default())
+    }
+
+    /// Take the value only if modified since last read, leaving the default
+    /// in its place and clearing the dirty flag.
+    pub fn take_if_dirty(&mut self) -> Option<T> {
+        match self.flag.get() {
+            true => {
+                self.clear();
+                Some(mem::take(&mut self.value))
+            },
+            false => None,
+        }
+    }
+}
+
+impl<T, D> Dirty<T, D> where T: PartialEq, D: DirtyFlag {
+    /// Set the value only if it differs from the current value, sets the
+    /// dirty flag when it does. Returns true if the value was set.
+    pub fn set_if_neq(&mut self, val: T) -> bool {
+        if self.value == val {
+            return false;
+        }
+        self.set(val);
+        true
+    }
+
+    /// Run f with a writable value, sets the dirty flag only if the value
+    /// differs from a copy taken before f ran. Returns true if it differs.
+    pub fn write_cmp<F>(&mut self, f: F) -> bool where T: Clone, F: FnOnce(&mut T) {
+        let old = self.value.clone();
+        f(&mut self.value);
+        if self.value == old {
+            return false;
+        }
+        self.mark();
+        true
+    }
+}
+
+impl<T, D> Dirty<Option<T>, D> where D: DirtyFlag {
+    /// Set the value to Some(val), sets the dirty flag.
+    pub fn set_some(&mut self, val: T) {
+        self.set(Some(val));
+    }
+
+    /// Take the value leaving None in its place, sets the dirty flag only if
+    /// the value was Some.
+    pub fn take_inner(&mut self) -> Option<T> {
+        let val = self.value.take();
+        if val.is_some() {
+            self.mark();
+        }
+        val
+    }
+
+    /// Returns true if the value is Some, false otherwise.
+    pub fn is_some(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Returns true if the value is None, false otherwise.
+    pub fn is_none(&self) -> bool {
+        self.value.is_none()
+    }
+
+    /// Writable value return, inserting the value returned by f if None. Sets
+    /// the dirty flag only on insertion.
+    pub fn get_or_insert_with<F>(&mut self, f: F) -> &mut T where F: FnOnce() -> T {
+        if self.value.is_none() {
+            self.mark();
+        }
+        self.value.get_or_insert_with(f)
+    }
+}
+
+/// DirtyWriteGuard gives scoped write access to the value of a Dirty.
+/// The dirty flag is set on drop if the guard was mutably dereferenced.
+/// A panic while the guard is held poisons the Dirty if the guard was
+/// mutably dereferenced.
+pub struct DirtyWriteGuard<'a, T: 'a + ?Sized, D: 'a + DirtyFlag = bool> {
+    dirty: &'a mut Dirty<T, D>,
+    modified: bool,
+}
+
+impl<'a, T, D> DirtyWriteGuard<'a, T, D> where T: ?Sized, D: DirtyFlag {
+    /// Returns true if the guard has been mutably dereferenced.
+    pub fn modified(&self) -> bool {
+        self.modified
+    }
+}
+
+impl<'a, T, D> Deref for DirtyWriteGuard<'a, T, D> where T: ?Sized, D: DirtyFlag {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.dirty.value
+    }
+}
+
+impl<'a, T, D> DerefMut for DirtyWriteGuard<'a, T, D> where T: ?Sized, D: DirtyFlag {
+    fn deref_mut(&mut self) -> &mut T {
+        self.modified = true;
+        &mut self.dirty.value
+    }
+}
+
+impl<'a, T, D> Drop for DirtyWriteGuard<'a, T, D> where T: ?Sized, D: DirtyFlag {
+    fn drop(&mut self) {
+        if self.modified {
+            self.dirty.mark();
+            #[cfg(feature = "std")]
+            {
+                if std::thread::panicking() {
+                    self.dirty.poisoned = true;
+                }
+            }
+        }
+    }
+}
+
+/// Sets the poisoned flag if dropped while panicking.
+#[cfg(feature = "std")]
+struct PoisonOnPanic<'a>(&'a mut bool);
+
+#[cfg(feature = "std")]
+impl<'a> Drop for PoisonOnPanic<'a> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            *self.0 = true;
+        }
+    }
+}
+
+impl<T, D> Default for Dirty<T, D> where T: Default, D: DirtyFlag {
     fn default() -> Self {
-        Dirty::new(T::default())
+        Dirty::from_flag(T::default(), D::new(true))
+    }
+}
+
+/// Create a new Dirty, like new() the value starts dirty.
+impl<T, D> From<T> for Dirty<T, D> where D: DirtyFlag {
+    fn from(val: T) -> Dirty<T, D> {
+        Dirty::from_flag(val, D::new(true))
+    }
+}
+
+impl<T, D> Clone for Dirty<T, D> where T: Clone, D: Clone {
+    fn clone(&self) -> Self {
+        Dirty {
+            value: self.value.clone(),
+            flag: self.flag.clone(),
+            poisoned: self.poisoned,
+            #[cfg(feature = "tracing")]
+            label: self.label,
+        }
+    }
+}
+
+impl<T, D> fmt::Debug for Dirty<T, D> where T: ?Sized + fmt::Debug, D: DirtyFlag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Dirty")
+            .field("value", &&self.value)
+            .field("dirty", &self.flag.get())
+            .finish()
+    }
+}
+
+impl<T, D> fmt::Display for Dirty<T, D> where T: ?Sized + fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// Compares the values only, a dirty and a clean Dirty holding equal values
+/// are equal.
+impl<T, D> PartialEq for Dirty<T, D> where T: ?Sized + PartialEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T, D> Eq for Dirty<T, D> where T: ?Sized + Eq {}
+
+/// Hashes the value only, consistent with PartialEq.
+impl<T, D> Hash for Dirty<T, D> where T: ?Sized + Hash {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Dirty;
+    use core::borrow::BorrowMut;
+    use core::cell::Cell;
+    use core::fmt;
+    use core::ops::Index;
+    use core::sync::atomic::AtomicBool;
+    use std::boxed::Box;
+    use std::collections::{HashMap, HashSet};
+    use std::string::String;
+    use std::vec::Vec;
 
     #[test]
     fn new_dirty() {
@@ -72,6 +835,29 @@ mod tests {
         assert!(dirty.dirty());
     }
 
+    #[test]
+    fn is_clean() {
+        let mut dirty = Dirty::new(0);
+        assert!(!dirty.is_clean());
+        dirty.clear();
+        assert!(dirty.is_clean());
+    }
+
+    #[test]
+    fn new_clean() {
+        let dirty = Dirty::new_clean(0);
+        assert!(!dirty.dirty());
+    }
+
+    #[test]
+    fn const_new() {
+        const DIRTY: Dirty<u32> = Dirty::new(1);
+        static CLEAN: Dirty<u32> = Dirty::new_clean(2);
+        assert!(DIRTY.dirty());
+        assert!(!CLEAN.dirty());
+        assert!(*CLEAN == 2);
+    }
+
     #[test]
     fn read_doesnt_clear_flag() {
         let dirty = Dirty::new(0);
@@ -91,6 +877,66 @@ mod tests {
     }
 
     #[test]
+    fn option_helpers() {
+        let mut dirty: Dirty<Option<i32>> = Dirty::new_clean(None);
+        assert!(dirty.take_inner().is_none());
+        assert!(!dirty.dirty());
+        assert!(*dirty.get_or_insert_with(|| 1) == 1);
+        assert!(dirty.dirty());
+        dirty.clear();
+        assert!(*dirty.get_or_insert_with(|| 2) == 1);
+        assert!(!dirty.dirty());
+        assert!(dirty.take_inner() == Some(1));
+        assert!(dirty.dirty() && dirty.is_none());
+        dirty.clear();
+        dirty.set_some(3);
+        assert!(dirty.dirty() && dirty.is_some());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn panic_in_update_poisons() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut dirty = Dirty::new_clean(1);
+        dirty.update(|v| *v += 1);
+        assert!(!dirty.is_poisoned());
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            dirty.update(|v| {
+                *v += 1;
+                panic!("half written");
+            });
+        }));
+        assert!(result.is_err());
+        assert!(dirty.is_poisoned() && *dirty == 3);
+        dirty.clear_poison();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut guard = dirty.write_guard();
+            *guard += 1;
+            panic!("half written");
+        }));
+        assert!(result.is_err());
+        assert!(dirty.is_poisoned());
+        dirty.clear_poison();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let guard = dirty.write_guard();
+            assert!(*guard == 4);
+            panic!("only read");
+        }));
+        assert!(result.is_err());
+        assert!(!dirty.is_poisoned());
+    }
+
+    #[test]
+    fn write_silent_keeps_flag() {
+        let mut dirty = Dirty::new_clean(0);
+        *dirty.write_silent() += 1;
+        assert!(!dirty.dirty());
+        assert!(*dirty == 1);
+    }
+
+    #[test]
+    #[allow(clippy::partialeq_to_none)]
     fn read_dirty() {
         let mut dirty = Dirty::new(0);
         assert!(dirty.read_dirty().is_some());
@@ -106,15 +952,403 @@ mod tests {
         assert!(dirty.read_dirty() == None);
     }
     
+    #[test]
+    fn take_dirty_clears_flag() {
+        let mut dirty = Dirty::new(0);
+        assert!(dirty.take_dirty() == Some(&0));
+        assert!(!dirty.dirty());
+        assert!(dirty.take_dirty().is_none());
+        *dirty.write() += 1;
+        assert!(dirty.take_dirty_cloned() == Some(1));
+        assert!(dirty.take_dirty_cloned().is_none());
+    }
+
+    #[test]
+    fn update_sets_flag() {
+        let mut dirty = Dirty::new(1);
+        dirty.clear();
+        dirty.update(|v| *v += 1).update(|v| *v *= 3);
+        assert!(dirty.dirty());
+        assert!(*dirty == 6);
+        dirty.clear();
+        assert!(dirty.update_and(|v| { *v += 1; *v }) == 7);
+        assert!(dirty.dirty());
+    }
+
+    #[test]
+    fn write_cmp() {
+        let mut dirty = Dirty::new(1);
+        dirty.clear();
+        assert!(!dirty.write_cmp(|v| *v = 1));
+        assert!(!dirty.dirty());
+        assert!(dirty.write_cmp(|v| *v += 1));
+        assert!(dirty.dirty());
+        assert!(*dirty == 2);
+    }
+
+    #[test]
+    fn replace_sets_flag() {
+        let mut dirty = Dirty::new(1);
+        dirty.clear();
+        assert!(dirty.replace(2) == 1);
+        assert!(dirty.dirty());
+        assert!(*dirty == 2);
+    }
+
+    #[test]
+    fn swap_marks_both() {
+        let mut front = Dirty::new_clean(1);
+        let mut back = Dirty::new_clean(2);
+        front.swap(&mut back);
+        assert!(*front == 2 && *back == 1);
+        assert!(front.dirty() && back.dirty());
+        front.clear();
+        let mut other = 3;
+        front.swap_value(&mut other);
+        assert!(*front == 3 && other == 2 && front.dirty());
+    }
+
+    #[test]
+    fn take_sets_flag() {
+        let mut dirty = Dirty::new(String::from("a"));
+        dirty.clear();
+        assert!(dirty.take() == "a");
+        assert!(dirty.dirty());
+        assert!(dirty.is_empty());
+    }
+
+    #[test]
+    fn take_if_dirty_drains() {
+        let mut batch = Dirty::new_clean(Vec::new());
+        assert!(batch.take_if_dirty().is_none());
+        batch.write().push(1);
+        batch.write().push(2);
+        assert!(batch.take_if_dirty() == Some(Vec::from([1, 2])));
+        assert!(!batch.dirty() && batch.is_empty());
+        assert!(batch.take_if_dirty().is_none());
+    }
+
+    #[test]
+    fn flush_clears_on_success() {
+        let mut dirty = Dirty::new(1);
+        let mut flushed = 0;
+        assert!(dirty.flush(|v| -> Result<(), ()> { flushed = *v; Ok(()) }) == Ok(true));
+        assert!(flushed == 1);
+        assert!(!dirty.dirty());
+        assert!(dirty.flush(|_| -> Result<(), ()> { panic!("flushed clean value") }) == Ok(false));
+    }
+
+    #[test]
+    fn flush_keeps_flag_on_error() {
+        let mut dirty = Dirty::new(1);
+        assert!(dirty.flush(|_| Err("disk full")) == Err("disk full"));
+        assert!(dirty.dirty());
+    }
+
+    #[test]
+    fn try_update_sets_flag_on_success() {
+        let mut dirty = Dirty::new_clean(1);
+        assert!(dirty.try_update(|v| if *v > 1 { Ok(()) } else { Err("too small") })
+                == Err("too small"));
+        assert!(!dirty.dirty() && *dirty == 1);
+        assert!(dirty.try_update(|v| -> Result<(), ()> { *v = 2; Ok(()) }) == Ok(()));
+        assert!(dirty.dirty());
+    }
+
+    #[test]
+    fn clear_if() {
+        let mut dirty = Dirty::new(1);
+        assert!(!dirty.clear_if(|v| *v > 1));
+        assert!(dirty.dirty());
+        assert!(dirty.clear_if(|v| *v == 1));
+        assert!(!dirty.dirty());
+        assert!(!dirty.clear_if(|_| panic!("checked clean value")));
+    }
+
+    #[test]
+    fn render_if_dirty() {
+        let mut dirty = Dirty::new(1);
+        assert!(dirty.render_if_dirty(|v| v * 2) == Some(2));
+        assert!(!dirty.dirty());
+        assert!(dirty.render_if_dirty(|_| panic!("rendered clean value")).is_none());
+    }
+
+    #[test]
+    fn map_keeps_flag() {
+        let mut dirty = Dirty::new(1);
+        assert!(Dirty::new(1).map(|v| v * 2).into_parts() == (2, true));
+        dirty.clear();
+        assert!(dirty.map(|v| v + 1).into_parts() == (2, false));
+    }
+
+    #[test]
+    fn as_dirty_ref_keeps_flag() {
+        let mut dirty = Dirty::new(1);
+        dirty.clear();
+        let view = dirty.as_dirty_ref();
+        assert!(!view.dirty());
+        assert!(**view == 1);
+    }
+
+    #[test]
+    fn as_dirty_mut_sets_flag() {
+        let mut dirty = Dirty::new(1);
+        dirty.clear();
+        {
+            let mut view = dirty.as_dirty_mut();
+            assert!(!view.dirty());
+            **view.write() += 1;
+            assert!(view.dirty());
+        }
+        assert!(dirty.dirty());
+        assert!(*dirty == 2);
+    }
+
     #[test]
     fn access_inner_deref() {
         let dirty = Dirty::new(0);
         assert!(*dirty == 0);
     }
     
+    #[test]
+    fn deref_mut_sets_flag() {
+        let mut dirty = Dirty::new(0);
+        dirty.clear();
+        assert!(!dirty.dirty());
+        *dirty += 1;
+        assert!(dirty.dirty());
+        assert!(*dirty == 1);
+    }
+
+    #[test]
+    fn into_inner() {
+        let mut dirty = Dirty::new(String::from("a"));
+        dirty.write().push('b');
+        assert!(dirty.into_inner() == "ab");
+    }
+
+    #[test]
+    fn into_parts() {
+        let mut dirty = Dirty::new(0);
+        assert!(dirty.into_parts() == (0, true));
+        dirty = Dirty::new(1);
+        dirty.clear();
+        assert!(dirty.into_parts() == (1, false));
+    }
+
+    #[test]
+    fn write_guard_read_only_stays_clean() {
+        let mut dirty = Dirty::new(0);
+        dirty.clear();
+        {
+            let guard = dirty.write_guard();
+            assert!(*guard == 0);
+            assert!(!guard.modified());
+        }
+        assert!(!dirty.dirty());
+    }
+
+    #[test]
+    fn write_guard_sets_flag_on_drop() {
+        let mut dirty = Dirty::new(0);
+        dirty.clear();
+        {
+            let mut guard = dirty.write_guard();
+            *guard += 1;
+            assert!(guard.modified());
+        }
+        assert!(dirty.dirty());
+        assert!(*dirty == 1);
+    }
+
+    #[test]
+    fn set_sets_flag() {
+        let mut dirty = Dirty::new(0);
+        dirty.clear();
+        dirty.set(0);
+        assert!(dirty.dirty());
+        assert!(*dirty == 0);
+    }
+
+    #[test]
+    fn set_if_neq() {
+        let mut dirty = Dirty::new(0);
+        dirty.clear();
+        assert!(!dirty.set_if_neq(0));
+        assert!(!dirty.dirty());
+        assert!(dirty.set_if_neq(1));
+        assert!(dirty.dirty());
+        assert!(*dirty == 1);
+    }
+
+    #[test]
+    fn clone_keeps_flag() {
+        let mut dirty = Dirty::new(1);
+        dirty.clear();
+        let cloned = dirty.clone();
+        assert!(!cloned.dirty());
+        assert!(*cloned == 1);
+    }
+
+    #[test]
+    fn debug_shows_flag() {
+        let dirty = Dirty::new(1);
+        assert!(std::format!("{:?}", dirty) == "Dirty { value: 1, dirty: true }");
+    }
+
+    #[test]
+    fn display_passes_through() {
+        let dirty = Dirty::new(1.5);
+        assert!(std::format!("{} {:.2}", dirty, dirty) == "1.5 1.50");
+    }
+
+    #[test]
+    fn eq_ignores_flag() {
+        let a = Dirty::new(1);
+        let b = Dirty::new_clean(1);
+        assert!(a == b);
+        assert!(a != Dirty::new(2));
+    }
+
+    #[test]
+    fn hash_matches_value() {
+        let mut set = HashSet::new();
+        set.insert(Dirty::new(1));
+        assert!(set.contains(&Dirty::new_clean(1)));
+    }
+
+    #[test]
+    fn from_parts() {
+        let (val, dirty) = Dirty::from_parts(1, false).into_parts();
+        assert!(val == 1 && !dirty);
+        assert!(Dirty::from_parts(2, true).dirty());
+    }
+
+    #[test]
+    fn from_value() {
+        let dirty: Dirty<i32> = 1.into();
+        assert!(dirty.dirty());
+        assert!(*dirty == 1);
+    }
+
+    #[test]
+    fn index_mut_sets_flag() {
+        fn first<C: Index<usize, Output = i32>>(c: &C) -> i32 {
+            c[0]
+        }
+        let mut values = Dirty::new_clean([1, 2, 3]);
+        assert!(first(&values) == 1 && values[1] == 2);
+        assert!(!values.dirty());
+        values[2] = 4;
+        assert!(values.dirty());
+        let mut map = Dirty::new_clean(HashMap::new());
+        map.write().insert("a", 1);
+        map.clear();
+        assert!(map["a"] == 1 && !map.dirty());
+    }
+
+    #[test]
+    fn as_ref_and_borrow() {
+        fn len<S: AsRef<str>>(s: S) -> usize {
+            s.as_ref().len()
+        }
+        let mut name = Dirty::new_clean(String::from("abc"));
+        assert!(len(&name) == 3);
+        assert!(AsRef::<str>::as_ref(&name) == "abc");
+        assert!(!name.dirty());
+        AsMut::<str>::as_mut(&mut name).make_ascii_uppercase();
+        assert!(name.dirty() && *name == "ABC");
+        name.clear();
+        BorrowMut::<String>::borrow_mut(&mut name).push('D');
+        assert!(name.dirty());
+        let mut set = HashSet::new();
+        set.insert(name);
+        assert!(set.contains(&String::from("ABCD")));
+    }
+
+    #[test]
+    fn unsized_values() {
+        let mut bytes = Dirty::new_clean([1u8, 2, 3]);
+        {
+            let slice: &mut Dirty<[u8]> = &mut bytes;
+            assert!(slice.len() == 3);
+            slice.write()[0] = 9;
+        }
+        assert!(bytes.dirty() && bytes[0] == 9);
+        let mut shown: Box<Dirty<dyn fmt::Display>> = Box::new(Dirty::new_clean(1));
+        assert!(std::format!("{}", shown.read()) == "1");
+        assert!(shown.read_dirty().is_none());
+        shown.write();
+        assert!(shown.dirty());
+    }
+
     #[test]
     fn default_value() {
         let dirty = Dirty::<i32>::default();
         assert!(*dirty == 0);
     }
+
+    #[test]
+    fn flag_backends() {
+        let shared: Dirty<i32, Cell<bool>> = Dirty::from(1);
+        assert!(shared.take_dirty_shared() == Some(&1));
+        assert!(shared.take_dirty_shared().is_none());
+        shared.mark_shared();
+        assert!(shared.dirty());
+        let mut atomic: Dirty<i32, AtomicBool> = Dirty::default();
+        *atomic.write() += 1;
+        atomic.clear_shared();
+        assert!(atomic.is_clean() && *atomic == 1);
+        let mut versioned = Dirty::from_flag(0, 0u64);
+        *versioned.write() += 1;
+        versioned.clear();
+        versioned.set(5);
+        assert!(versioned.dirty() && versioned.version() == 2);
+    }
+
+    #[cfg(all(feature = "std", feature = "tracing"))]
+    #[test]
+    fn traces_transitions() {
+        use std::sync::{Arc, Mutex};
+        use std::vec::Vec;
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        struct Events(Arc<Mutex<Vec<String>>>);
+
+        impl Visit for Events {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                self.0.lock().unwrap().push(std::format!("{}={:?}", field.name(), value));
+            }
+        }
+
+        impl Subscriber for Events {
+            fn enabled(&self, _: &Metadata) -> bool { true }
+            fn new_span(&self, _: &Attributes) -> Id { Id::from_u64(1) }
+            fn record(&self, _: &Id, _: &Record) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event) {
+                event.record(&mut Events(self.0.clone()));
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Events(events.clone()), || {
+            let mut dirty = Dirty::new_clean(0).with_label("counter");
+            *dirty.write() += 1;
+            *dirty.write() += 1;
+            dirty.clear();
+            dirty.clear();
+            dirty.set(5);
+            assert!(dirty.flush(|_| Ok::<(), ()>(())) == Ok(true));
+        });
+        let events = events.lock().unwrap();
+        let messages: Vec<&String> = events.iter().filter(|e| e.starts_with("message=")).collect();
+        assert!(messages == ["message=dirtied", "message=cleared", "message=dirtied",
+                             "message=flushed"]);
+        assert!(events.iter().filter(|e| *e == "label=\"counter\"").count() == 4);
+    }
 }
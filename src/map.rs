@@ -0,0 +1,186 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::hash::Hash;
+use std::ops::Deref;
+use std::vec::Vec;
+
+use change::{KeyChange, State};
+
+/// DirtyHashMap wraps a HashMap<K, V> recording which keys were inserted,
+/// updated or removed since the last clear().
+/// Use deref (dirty_map.get(k)) to read the inner map.
+pub struct DirtyHashMap<K, V> {
+    map: HashMap<K, V>,
+    changes: HashMap<K, State>,
+}
+
+impl<K, V> DirtyHashMap<K, V> where K: Eq + Hash + Clone {
+    /// Create a new empty DirtyHashMap.
+    pub fn new() -> DirtyHashMap<K, V> {
+        DirtyHashMap {
+            map: HashMap::new(),
+            changes: HashMap::new(),
+        }
+    }
+
+    /// Returns true if any key changed since the last clear(), false otherwise.
+    pub fn dirty(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// Inserts a value, recording the key as inserted or updated.
+    pub fn insert(&mut self, key: K, val: V) -> Option<V> {
+        let old = self.map.insert(key.clone(), val);
+        let state = State::after_insert(old.is_some(), self.changes.get(&key).cloned());
+        self.changes.insert(key, state);
+        old
+    }
+
+    /// Writable value return, records the key as updated.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>, Q: ?Sized + Eq + Hash
+    {
+        if let Some((k, _)) = self.map.get_key_value(key) {
+            self.changes.entry(k.clone()).or_insert(State::Updated);
+        }
+        self.map.get_mut(key)
+    }
+
+    /// Removes a key, recording it as removed unless it was inserted since the
+    /// last clear().
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: ?Sized + Eq + Hash
+    {
+        let (k, val) = self.map.remove_entry(key)?;
+        match self.changes.entry(k) {
+            Entry::Occupied(entry) => {
+                if *entry.get() == State::Inserted {
+                    entry.remove();
+                } else {
+                    *entry.into_mut() = State::Removed;
+                }
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(State::Removed);
+            },
+        }
+        Some(val)
+    }
+
+    /// Iterate over the keys inserted or updated since the last clear().
+    pub fn dirty_keys(&self) -> impl Iterator<Item = &K> {
+        self.changes.iter()
+            .filter(|&(_, s)| *s != State::Removed)
+            .map(|(k, _)| k)
+    }
+
+    /// Iterate over the keys removed since the last clear().
+    pub fn removed_keys(&self) -> impl Iterator<Item = &K> {
+        self.changes.iter()
+            .filter(|&(_, s)| *s == State::Removed)
+            .map(|(k, _)| k)
+    }
+
+    /// Returns every change since the last clear() and clears them.
+    pub fn drain_changes(&mut self) -> Vec<KeyChange<K>> {
+        self.changes.drain()
+            .map(|(k, s)| s.into_change(k))
+            .collect()
+    }
+
+    /// Clears the recorded changes.
+    pub fn clear(&mut self) {
+        self.changes.clear();
+    }
+
+    /// Consumes the DirtyHashMap, returning the inner HashMap.
+    pub fn into_inner(self) -> HashMap<K, V> {
+        self.map
+    }
+}
+
+impl<K, V> From<HashMap<K, V>> for DirtyHashMap<K, V> where K: Eq + Hash + Clone {
+    /// Create a new DirtyHashMap with every key recorded as inserted.
+    fn from(map: HashMap<K, V>) -> DirtyHashMap<K, V> {
+        DirtyHashMap {
+            changes: map.keys().map(|k| (k.clone(), State::Inserted)).collect(),
+            map,
+        }
+    }
+}
+
+impl<K, V> Deref for DirtyHashMap<K, V> {
+    type Target = HashMap<K, V>;
+    fn deref(&self) -> &HashMap<K, V> {
+        &self.map
+    }
+}
+
+impl<K, V> Default for DirtyHashMap<K, V> where K: Eq + Hash + Clone {
+    fn default() -> Self {
+        DirtyHashMap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtyHashMap;
+    use change::KeyChange;
+    use std::vec::Vec;
+
+    fn clean() -> DirtyHashMap<&'static str, i32> {
+        let mut map = DirtyHashMap::new();
+        map.insert("a", 1);
+        map.insert("b", 2);
+        map.clear();
+        map
+    }
+
+    fn sorted(mut changes: Vec<KeyChange<&'static str>>) -> Vec<KeyChange<&'static str>> {
+        changes.sort_by_key(|c| match *c {
+            KeyChange::Inserted(k) | KeyChange::Updated(k) | KeyChange::Removed(k) => k,
+        });
+        changes
+    }
+
+    #[test]
+    fn insert_records_inserted_and_updated() {
+        let mut map = clean();
+        assert!(!map.dirty());
+        map.insert("b", 3);
+        map.insert("c", 4);
+        assert!(map.dirty());
+        assert!(sorted(map.drain_changes())
+                == vec![KeyChange::Updated("b"), KeyChange::Inserted("c")]);
+        assert!(!map.dirty());
+    }
+
+    #[test]
+    fn get_mut_records_updated() {
+        let mut map = clean();
+        *map.get_mut("a").unwrap() += 1;
+        assert!(map.get_mut("z").is_none());
+        assert!(map.dirty_keys().collect::<Vec<_>>() == vec![&"a"]);
+        assert!(map["a"] == 2);
+    }
+
+    #[test]
+    fn remove_records_removed() {
+        let mut map = clean();
+        map.remove("a");
+        assert!(map.removed_keys().collect::<Vec<_>>() == vec![&"a"]);
+        assert!(map.dirty_keys().next().is_none());
+        map.insert("a", 5);
+        assert!(map.drain_changes() == vec![KeyChange::Updated("a")]);
+    }
+
+    #[test]
+    fn remove_inserted_is_no_change() {
+        let mut map = clean();
+        map.insert("c", 3);
+        map.remove("c");
+        assert!(!map.dirty());
+        assert!(map.remove("c").is_none());
+    }
+}
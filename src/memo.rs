@@ -0,0 +1,139 @@
+use track::Tracked;
+
+/// Memo caches the output of a computation over dirty sources, recomputing
+/// only when a source is dirty.
+/// Sources are any Tracked value, or a tuple of them, written through
+/// sources_mut().
+pub struct Memo<S, O, F> where S: Tracked, F: FnMut(&S) -> O {
+    sources: S,
+    output: Option<O>,
+    compute: F,
+}
+
+impl<S, O, F> Memo<S, O, F> where S: Tracked, F: FnMut(&S) -> O {
+    /// Create a new Memo, the output is computed on first get().
+    pub fn new(sources: S, compute: F) -> Memo<S, O, F> {
+        Memo {
+            sources,
+            output: None,
+            compute,
+        }
+    }
+
+    /// Returns true if the next get() recomputes, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.output.is_none() || self.sources.dirty()
+    }
+
+    /// Read the sources.
+    pub fn sources(&self) -> &S {
+        &self.sources
+    }
+
+    /// Writable sources return.
+    pub fn sources_mut(&mut self) -> &mut S {
+        &mut self.sources
+    }
+
+    /// Returns the output, recomputing it and clearing the sources if any
+    /// source is dirty.
+    pub fn get(&mut self) -> &O {
+        if self.dirty() {
+            self.output = Some((self.compute)(&self.sources));
+            self.sources.clear();
+        }
+        self.output.as_ref().unwrap()
+    }
+
+    /// Returns the cached output without recomputing, None if never computed.
+    pub fn cached(&self) -> Option<&O> {
+        self.output.as_ref()
+    }
+
+    /// Drops the cached output so the next get() recomputes.
+    pub fn invalidate(&mut self) {
+        self.output = None;
+    }
+
+    /// Consumes the Memo, returning the sources.
+    pub fn into_sources(self) -> S {
+        self.sources
+    }
+}
+
+/// Creates a Memo over dirty inputs from a closure naming them, such as
+/// `computed!(|a: &Dirty<i32>, b: &Dirty<i32>| *a.read() + *b.read())`.
+/// The variables named by the parameters are moved into the Memo as its
+/// sources, up to eight of them, and the Memo recomputes the body only when an
+/// input is dirty. Write the inputs through sources_mut().
+#[macro_export]
+macro_rules! computed {
+    (|$name:ident : & $ty:ty| $body:expr) => {
+        $crate::Memo::new($name, |$name: &$ty| $body)
+    };
+    (|$($name:ident : & $ty:ty),+| $body:expr) => {
+        $crate::Memo::new(($($name,)+), |sources: &($($ty,)+)| {
+            let ($(ref $name,)+) = *sources;
+            $body
+        })
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Memo;
+    use std::cell::Cell;
+    use Dirty;
+
+    #[test]
+    fn recompute_only_when_dirty() {
+        let runs = Cell::new(0);
+        let mut memo = Memo::new((Dirty::new(2), Dirty::new(3)), |s: &(Dirty<i32>, Dirty<i32>)| {
+            runs.set(runs.get() + 1);
+            *s.0 * *s.1
+        });
+        assert!(memo.cached().is_none());
+        assert!(*memo.get() == 6);
+        assert!(*memo.get() == 6);
+        assert!(runs.get() == 1);
+        *memo.sources_mut().1.write() = 4;
+        assert!(memo.dirty());
+        assert!(*memo.get() == 8);
+        assert!(runs.get() == 2);
+        memo.invalidate();
+        assert!(*memo.get() == 8);
+        assert!(runs.get() == 3);
+    }
+
+    #[test]
+    fn computed_macro() {
+        let runs = Cell::new(0);
+        let a = Dirty::new(2);
+        let b = Dirty::new(3);
+        let mut sum = computed!(|a: &Dirty<i32>, b: &Dirty<_>| {
+            runs.set(runs.get() + 1);
+            *a.read() + *b.read()
+        });
+        assert!(*sum.get() == 5);
+        assert!(*sum.get() == 5 && runs.get() == 1);
+        sum.sources_mut().0.set(10);
+        assert!(*sum.get() == 13 && runs.get() == 2);
+        let n = Dirty::new(4);
+        let mut double = computed!(|n: &Dirty<i32>| *n.read() * 2);
+        assert!(*double.get() == 8 && !double.dirty());
+    }
+
+    #[test]
+    fn computed_macro_eight_inputs() {
+        let (a, b, c, d) = (Dirty::new(1), Dirty::new(2), Dirty::new(3), Dirty::new(4));
+        let (e, f, g, h) = (Dirty::new(5), Dirty::new(6), Dirty::new(7), Dirty::new(8));
+        let mut sum = computed!(|a: &Dirty<i32>, b: &Dirty<i32>, c: &Dirty<i32>, d: &Dirty<i32>,
+                                 e: &Dirty<i32>, f: &Dirty<i32>, g: &Dirty<i32>, h: &Dirty<i32>| {
+            *a.read() + *b.read() + *c.read() + *d.read() + *e.read() + *f.read() + *g.read() + *h.read()
+        });
+        assert!(*sum.get() == 36 && !sum.dirty());
+        sum.sources_mut().7.set(18);
+        assert!(sum.dirty());
+        assert!(*sum.get() == 46);
+    }
+}
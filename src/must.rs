@@ -0,0 +1,143 @@
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ops::Deref;
+use core::ptr;
+
+use Dirty;
+
+/// MustFlush wraps a value of type T like Dirty and complains if dropped
+/// while dirty, catching pending changes silently lost when a value goes out
+/// of scope. With debug assertions and std such a drop panics, unless already
+/// panicking, and with the tracing feature it logs a warning.
+/// Use discard() or into_inner() to drop changes on purpose.
+pub struct MustFlush<T> {
+    value: Dirty<T>,
+}
+
+impl<T> MustFlush<T> {
+    /// Create a new MustFlush.
+    pub fn new(val: T) -> MustFlush<T> {
+        MustFlush {
+            value: Dirty::new(val),
+        }
+    }
+
+    /// Create a new clean MustFlush.
+    pub fn new_clean(val: T) -> MustFlush<T> {
+        MustFlush {
+            value: Dirty::new_clean(val),
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.value.dirty()
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.value.write()
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        self.value.set(val);
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        self.value.read()
+    }
+
+    /// Clears the dirty flag.
+    pub fn clear(&mut self) {
+        self.value.clear();
+    }
+
+    /// Calls f with the value if dirty, clearing the dirty flag only if f
+    /// returns Ok. Returns true if flushed, false if already clean.
+    pub fn flush<E, F>(&mut self, f: F) -> Result<bool, E>
+        where F: FnOnce(&T) -> Result<(), E>
+    {
+        self.value.flush(f)
+    }
+
+    /// Drops the value, discarding any pending changes without complaint.
+    pub fn discard(self) {
+        self.into_dirty();
+    }
+
+    /// Consumes the MustFlush, returning the inner Dirty without complaint.
+    pub fn into_dirty(self) -> Dirty<T> {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: this is never dropped, so the value is moved out only once.
+        unsafe { ptr::read(&this.value) }
+    }
+
+    /// Consumes the MustFlush, returning the inner value without complaint.
+    pub fn into_inner(self) -> T {
+        self.into_dirty().into_inner()
+    }
+}
+
+impl<T> From<Dirty<T>> for MustFlush<T> {
+    fn from(value: Dirty<T>) -> MustFlush<T> {
+        MustFlush { value }
+    }
+}
+
+impl<T> Deref for MustFlush<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value.read()
+    }
+}
+
+impl<T> Drop for MustFlush<T> {
+    fn drop(&mut self) {
+        if self.value.dirty() {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(value = core::any::type_name::<T>(), "MustFlush dropped while dirty");
+            #[cfg(all(debug_assertions, feature = "std"))]
+            {
+                if !std::thread::panicking() {
+                    panic!("MustFlush<{}> dropped while dirty", core::any::type_name::<T>());
+                }
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for MustFlush<T> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MustFlush")
+            .field("value", self.value.read())
+            .field("dirty", &self.value.dirty())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MustFlush;
+
+    #[test]
+    fn clean_drop_is_quiet() {
+        let mut pending = MustFlush::new_clean(1);
+        pending.set(2);
+        assert!(pending.flush(|_| -> Result<(), ()> { Ok(()) }) == Ok(true));
+        drop(pending);
+        let mut discarded = MustFlush::new(1);
+        *discarded.write() += 1;
+        discarded.discard();
+        assert!(MustFlush::new(3).into_inner() == 3);
+    }
+
+    #[cfg(all(debug_assertions, feature = "std"))]
+    #[test]
+    #[should_panic(expected = "dropped while dirty")]
+    fn dirty_drop_panics() {
+        let mut pending = MustFlush::new_clean(1);
+        pending.set(2);
+    }
+}
@@ -0,0 +1,170 @@
+use core::ops::{Deref, DerefMut};
+
+/// ObservedDirty wraps a value of type T like Dirty and calls an observer with
+/// the value each time it transitions from clean to dirty, so consumers may
+/// react to changes rather than poll dirty().
+/// Like Dirty, a new ObservedDirty starts dirty, the observer is first called
+/// on the first write after a clear().
+pub struct ObservedDirty<T, F> where F: FnMut(&T) {
+    value: T,
+    dirty: bool,
+    observer: F,
+}
+
+impl<T, F> ObservedDirty<T, F> where F: FnMut(&T) {
+    /// Create a new ObservedDirty calling observer on clean to dirty
+    /// transitions.
+    pub fn new(val: T, observer: F) -> ObservedDirty<T, F> {
+        ObservedDirty {
+            value: val,
+            dirty: true,
+            observer,
+        }
+    }
+
+    /// Create a new clean ObservedDirty calling observer on clean to dirty
+    /// transitions.
+    pub fn new_clean(val: T, observer: F) -> ObservedDirty<T, F> {
+        ObservedDirty {
+            value: val,
+            dirty: false,
+            observer,
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Writable guard return, sets the dirty flag and calls the observer when
+    /// the guard is dropped if it was mutably dereferenced.
+    pub fn write(&mut self) -> ObservedWriteGuard<'_, T, F> {
+        ObservedWriteGuard {
+            observed: self,
+            modified: false,
+        }
+    }
+
+    /// Set the value, sets the dirty flag and calls the observer if clean.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.mark();
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Clears the dirty flag.
+    pub fn clear(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Read the value only if modified since last read.
+    pub fn read_dirty(&self) -> Option<&T> {
+        match self.dirty {
+            true => Some(&self.value),
+            false => None,
+        }
+    }
+
+    /// Consumes the ObservedDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    fn mark(&mut self) {
+        if !self.dirty {
+            self.dirty = true;
+            (self.observer)(&self.value);
+        }
+    }
+}
+
+impl<T, F> Deref for ObservedDirty<T, F> where F: FnMut(&T) {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// ObservedWriteGuard gives scoped write access to the value of an
+/// ObservedDirty, the observer sees the value after the guard is dropped.
+pub struct ObservedWriteGuard<'a, T: 'a, F: 'a> where F: FnMut(&T) {
+    observed: &'a mut ObservedDirty<T, F>,
+    modified: bool,
+}
+
+impl<'a, T, F> Deref for ObservedWriteGuard<'a, T, F> where F: FnMut(&T) {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.observed.value
+    }
+}
+
+impl<'a, T, F> DerefMut for ObservedWriteGuard<'a, T, F> where F: FnMut(&T) {
+    fn deref_mut(&mut self) -> &mut T {
+        self.modified = true;
+        &mut self.observed.value
+    }
+}
+
+impl<'a, T, F> Drop for ObservedWriteGuard<'a, T, F> where F: FnMut(&T) {
+    fn drop(&mut self) {
+        if self.modified {
+            self.observed.mark();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ObservedDirty;
+    use std::cell::Cell;
+
+    #[test]
+    fn observer_called_on_transition() {
+        let seen = Cell::new(None);
+        let mut dirty = ObservedDirty::new(0, |v: &i32| seen.set(Some(*v)));
+        *dirty.write() += 1;
+        assert!(seen.get().is_none());
+        dirty.clear();
+        *dirty.write() += 1;
+        assert!(seen.get() == Some(2));
+        assert!(dirty.dirty());
+    }
+
+    #[test]
+    fn new_clean_notifies_first_write() {
+        let seen = Cell::new(None);
+        let mut dirty = ObservedDirty::new_clean(0, |v: &i32| seen.set(Some(*v)));
+        assert!(!dirty.dirty());
+        dirty.set(1);
+        assert!(seen.get() == Some(1));
+    }
+
+    #[test]
+    fn observer_called_once_while_dirty() {
+        let calls = Cell::new(0);
+        let mut dirty = ObservedDirty::new(0, |_: &i32| calls.set(calls.get() + 1));
+        dirty.clear();
+        dirty.set(1);
+        dirty.set(2);
+        assert!(calls.get() == 1);
+        dirty.clear();
+        dirty.set(3);
+        assert!(calls.get() == 2);
+    }
+
+    #[test]
+    fn read_only_guard_doesnt_notify() {
+        let calls = Cell::new(0);
+        let mut dirty = ObservedDirty::new(0, |_: &i32| calls.set(calls.get() + 1));
+        dirty.clear();
+        assert!(*dirty.write() == 0);
+        assert!(calls.get() == 0);
+        assert!(!dirty.dirty());
+    }
+}
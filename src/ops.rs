@@ -0,0 +1,46 @@
+use core::ops::{AddAssign, BitAndAssign, BitOrAssign, BitXorAssign, DivAssign, MulAssign,
+                RemAssign, ShlAssign, ShrAssign, SubAssign};
+
+use flag::DirtyFlag;
+use Dirty;
+
+macro_rules! assign_op {
+    ($trait:ident, $method:ident) => {
+        /// Applies the operator to the value, sets the dirty flag.
+        impl<T, D, Rhs> $trait<Rhs> for Dirty<T, D> where T: ?Sized + $trait<Rhs>, D: DirtyFlag {
+            fn $method(&mut self, rhs: Rhs) {
+                self.write().$method(rhs);
+            }
+        }
+    };
+}
+
+assign_op!(AddAssign, add_assign);
+assign_op!(SubAssign, sub_assign);
+assign_op!(MulAssign, mul_assign);
+assign_op!(DivAssign, div_assign);
+assign_op!(RemAssign, rem_assign);
+assign_op!(BitAndAssign, bitand_assign);
+assign_op!(BitOrAssign, bitor_assign);
+assign_op!(BitXorAssign, bitxor_assign);
+assign_op!(ShlAssign, shl_assign);
+assign_op!(ShrAssign, shr_assign);
+
+#[cfg(test)]
+mod tests {
+    use Dirty;
+
+    #[test]
+    fn assign_ops_set_flag() {
+        let mut counter = Dirty::new_clean(1);
+        counter += 4;
+        assert!(counter.dirty());
+        counter.clear();
+        counter *= 3;
+        counter -= 5;
+        counter <<= 1;
+        counter |= 1;
+        assert!(counter.dirty());
+        assert!(*counter == 21);
+    }
+}
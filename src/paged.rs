@@ -0,0 +1,162 @@
+use alloc::vec::Vec;
+use core::cmp;
+use core::ops::Range;
+
+use bits::{self, SetIndices};
+
+/// PagedDirty tracks which pages of PAGE bytes of a memory region, e.g. a
+/// memory mapped file, were written since they were last cleared.
+/// Writes are reported with mark(), so the region itself may live anywhere.
+/// A new PagedDirty starts with every page dirty.
+pub struct PagedDirty<const PAGE: usize = 4096> {
+    len: usize,
+    pages: Vec<u64>,
+}
+
+impl<const PAGE: usize> PagedDirty<PAGE> {
+    /// Create a new PagedDirty for a region of len bytes.
+    pub fn new(len: usize) -> PagedDirty<PAGE> {
+        assert!(PAGE > 0, "page size must be non zero");
+        let mut paged = PagedDirty {
+            len: 0,
+            pages: Vec::new(),
+        };
+        paged.resize(len);
+        paged.mark(0, len);
+        paged
+    }
+
+    /// Returns the page size in bytes.
+    pub fn page_size(&self) -> usize {
+        PAGE
+    }
+
+    /// Returns the length of the region in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the region is empty, false otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of pages covering the region.
+    pub fn page_count(&self) -> usize {
+        self.len.div_ceil(PAGE)
+    }
+
+    /// Returns the byte range of page i, the last page may be short.
+    pub fn page_range(&self, i: usize) -> Range<usize> {
+        (i * PAGE)..cmp::min((i + 1) * PAGE, self.len)
+    }
+
+    /// Resizes the region to len bytes, pages added are clean and pages past
+    /// the new end are dropped.
+    pub fn resize(&mut self, len: usize) {
+        self.len = len;
+        let count = self.page_count();
+        self.pages.resize(count.div_ceil(64), 0);
+        if !count.is_multiple_of(64) {
+            if let Some(last) = self.pages.last_mut() {
+                *last &= (1 << (count % 64)) - 1;
+            }
+        }
+    }
+
+    /// Returns true if any page is dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.pages.iter().any(|&w| w != 0)
+    }
+
+    /// Returns true if page i is dirty, false otherwise.
+    pub fn page_dirty(&self, i: usize) -> bool {
+        i < self.page_count() && self.pages[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// Marks the pages touched by a write of len bytes at offset dirty,
+    /// clipped to the region.
+    pub fn mark(&mut self, offset: usize, len: usize) {
+        let end = cmp::min(offset.saturating_add(len), self.len);
+        if offset >= end {
+            return;
+        }
+        for i in (offset / PAGE)..((end - 1) / PAGE + 1) {
+            self.pages[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    /// Iterate over the indices of the dirty pages in increasing order.
+    pub fn dirty_pages(&self) -> SetIndices<'_> {
+        SetIndices::new(&self.pages)
+    }
+
+    /// Returns the index of the first dirty page at or after page from.
+    pub fn find_next_dirty(&self, from: usize) -> Option<usize> {
+        bits::find_next(&self.pages, from)
+    }
+
+    /// Returns the number of dirty pages.
+    pub fn count_dirty(&self) -> usize {
+        bits::count(&self.pages)
+    }
+
+    /// Clears page i.
+    pub fn clear_page(&mut self, i: usize) {
+        if i < self.page_count() {
+            self.pages[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    /// Clears every page.
+    pub fn clear(&mut self) {
+        for w in self.pages.iter_mut() {
+            *w = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PagedDirty;
+    use alloc::vec::Vec;
+
+    fn clean(len: usize) -> PagedDirty<4096> {
+        let mut paged = PagedDirty::new(len);
+        paged.clear();
+        paged
+    }
+
+    #[test]
+    fn new_all_dirty() {
+        let paged: PagedDirty = PagedDirty::new(4096 * 70 + 1);
+        assert!(paged.page_count() == 71);
+        assert!(paged.dirty_pages().count() == 71);
+        assert!(paged.page_range(70) == ((4096 * 70)..(4096 * 70 + 1)));
+    }
+
+    #[test]
+    fn mark_touched_pages() {
+        let mut paged = clean(4096 * 100);
+        assert!(!paged.dirty());
+        paged.mark(4095, 2);
+        paged.mark(4096 * 65, 4096);
+        paged.mark(4096 * 200, 1);
+        paged.mark(10, 0);
+        assert!(paged.dirty_pages().collect::<Vec<usize>>() == [0, 1, 65]);
+        assert!(paged.count_dirty() == 3 && paged.find_next_dirty(2) == Some(65));
+        paged.clear_page(1);
+        assert!(!paged.page_dirty(1));
+        assert!(paged.dirty_pages().collect::<Vec<usize>>() == [0, 65]);
+    }
+
+    #[test]
+    fn resize_drops_pages() {
+        let mut paged = PagedDirty::<16>::new(16 * 10);
+        paged.resize(16 * 3);
+        assert!(paged.dirty_pages().collect::<Vec<usize>>() == [0, 1, 2]);
+        paged.resize(16 * 5);
+        assert!(!paged.page_dirty(4));
+        assert!(paged.page_size() == 16);
+    }
+}
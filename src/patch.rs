@@ -0,0 +1,103 @@
+use serde::ser::Error;
+use serde::Serialize;
+use serde_json::{self, Map, Value};
+use std::string::String;
+
+use fields::{DirtyFields, StructFields};
+
+/// MergePatch turns the dirty fields of a StructFields tracker into a RFC 7386
+/// JSON Merge Patch, an object holding only the dirty fields.
+/// Fields are assumed to serialize as object members named as in FIELDS, so
+/// serde renames are not followed. A dirty field missing from the serialized
+/// struct, such as a skipped None or a default left out by
+/// skip_serializing_if, is null so the receiver removes it.
+pub trait MergePatch: StructFields where Self::Inner: Serialize {
+    /// Returns a merge patch of the dirty fields.
+    fn to_merge_patch(&self) -> serde_json::Result<Value> {
+        let mut new = to_object(self.inner())?;
+        let patch = dirty_members(self)
+            .map(|name| (name.into(), new.remove(name).unwrap_or(Value::Null)))
+            .collect();
+        Ok(Value::Object(patch))
+    }
+}
+
+impl<F> MergePatch for F where F: StructFields, F::Inner: Serialize {}
+
+/// JsonPatch turns the dirty fields of a StructFields tracker into a RFC 6902
+/// JSON Patch document, an array of operations on the serialized struct.
+/// Fields are assumed to serialize as object members named as in FIELDS, so
+/// serde renames are not followed. A dirty field missing from the serialized
+/// struct, such as a skipped None, becomes a remove.
+#[cfg(feature = "json-patch")]
+pub trait JsonPatch: StructFields where Self::Inner: Serialize {
+    /// Returns a patch setting every dirty field. Fields are set with add,
+    /// which replaces a member the target already has and creates a missing
+    /// one. A remove fails on a target missing the member, use
+    /// to_json_patch_from() when the target may not have every field.
+    fn to_json_patch(&self) -> serde_json::Result<Value> {
+        let new = to_object(self.inner())?;
+        let ops = dirty_members(self)
+            .map(|name| match new.get(name) {
+                Some(val) => op("add", name, Some(val.clone())),
+                None => op("remove", name, None),
+            })
+            .collect();
+        Ok(Value::Array(ops))
+    }
+
+    /// Returns a patch from old to the tracked struct over the dirty fields,
+    /// adding fields missing from old and skipping fields left equal to old.
+    fn to_json_patch_from(&self, old: &Self::Inner) -> serde_json::Result<Value> {
+        let old = to_object(old)?;
+        let new = to_object(self.inner())?;
+        let ops = dirty_members(self)
+            .filter_map(|name| match (old.get(name), new.get(name)) {
+                (Some(old), Some(new)) if old == new => None,
+                (Some(_), Some(new)) => Some(op("replace", name, Some(new.clone()))),
+                (None, Some(new)) => Some(op("add", name, Some(new.clone()))),
+                (Some(_), None) => Some(op("remove", name, None)),
+                (None, None) => None,
+            })
+            .collect();
+        Ok(Value::Array(ops))
+    }
+}
+
+#[cfg(feature = "json-patch")]
+impl<F> JsonPatch for F where F: StructFields, F::Inner: Serialize {}
+
+pub(crate) fn dirty_members<F>(fields: &F) -> impl Iterator<Item = &'static str> + '_
+    where F: ?Sized + DirtyFields
+{
+    F::FIELDS.iter()
+        .enumerate()
+        .filter(move |&(i, _)| fields.field_dirty(i))
+        .map(|(_, name)| *name)
+}
+
+pub(crate) fn to_object<T>(val: &T) -> serde_json::Result<Map<String, Value>> where T: Serialize {
+    match serde_json::to_value(val)? {
+        Value::Object(map) => Ok(map),
+        _ => Err(serde_json::Error::custom("patches require a struct serialized as an object")),
+    }
+}
+
+#[cfg(feature = "json-patch")]
+fn op(op: &str, name: &str, value: Option<Value>) -> Value {
+    let mut map = Map::new();
+    map.insert("op".into(), op.into());
+    map.insert("path".into(), pointer(name).into());
+    if let Some(value) = value {
+        map.insert("value".into(), value);
+    }
+    Value::Object(map)
+}
+
+/// Returns the JSON Pointer (RFC 6901) to the member name.
+#[cfg(feature = "json-patch")]
+fn pointer(name: &str) -> String {
+    let mut path = String::from("/");
+    path.push_str(&name.replace('~', "~0").replace('/', "~1"));
+    path
+}
@@ -0,0 +1,57 @@
+use core::pin::Pin;
+
+use flag::DirtyFlag;
+use Dirty;
+
+/// The value of a Dirty is structurally pinned, a pinned Dirty pins its value
+/// while the dirty flag is not pinned. This holds as Dirty is only Unpin when
+/// T is, has no Drop impl, is not packed, and offers no way to move the value
+/// out of a `Pin<&mut Dirty<T>>`.
+impl<T, D> Dirty<T, D> where T: ?Sized, D: DirtyFlag {
+    /// Pinned value return from a pinned Dirty.
+    pub fn as_pin_ref(self: Pin<&Self>) -> Pin<&T> {
+        // SAFETY: the value is structurally pinned, see above.
+        unsafe { self.map_unchecked(|dirty| &dirty.value) }
+    }
+
+    /// Pinned writable value return from a pinned Dirty, sets the dirty flag.
+    pub fn as_pin_mut(self: Pin<&mut Self>) -> Pin<&mut T> {
+        // SAFETY: the value is structurally pinned, see above, and write()
+        // does not move it.
+        unsafe { self.map_unchecked_mut(|dirty| dirty.write()) }
+    }
+
+    /// Clears the dirty flag of a pinned Dirty.
+    pub fn clear_pinned(self: Pin<&mut Self>) {
+        // SAFETY: only the flag, which is not pinned, is written.
+        unsafe { self.get_unchecked_mut().clear() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::future::{self, Future};
+    use core::marker::PhantomPinned;
+    use core::pin::Pin;
+    use core::task::{Context, Poll, Waker};
+    use std::boxed::Box;
+    use Dirty;
+
+    #[test]
+    fn poll_pinned_future() {
+        let mut dirty = Box::pin(Dirty::new_clean(future::ready(5)));
+        assert!(!dirty.dirty());
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(dirty.as_mut().as_pin_mut().poll(&mut cx) == Poll::Ready(5));
+        assert!(dirty.dirty());
+        dirty.as_mut().clear_pinned();
+        assert!(dirty.is_clean());
+    }
+
+    #[test]
+    fn pins_values_not_unpin() {
+        let pinned = Box::pin(Dirty::new((1, PhantomPinned)));
+        let value: Pin<&(i32, PhantomPinned)> = pinned.as_ref().as_pin_ref();
+        assert!(value.0 == 1);
+    }
+}
@@ -0,0 +1,40 @@
+use bytemuck::Pod;
+use core::mem;
+
+/// Iterator over the byte views of the runs of consecutive dirty elements of
+/// a per element container, yielding the byte offset of each run into the
+/// values and its bytes, so a delta may be copied to GPU or shared memory.
+pub(crate) struct DirtyBytes<'a, T, I> {
+    values: &'a [T],
+    indices: I,
+    next: Option<usize>,
+}
+
+impl<'a, T, I> DirtyBytes<'a, T, I> where I: Iterator<Item = usize> {
+    /// Create a DirtyBytes over values from the increasing dirty indices.
+    pub(crate) fn new(values: &'a [T], mut indices: I) -> DirtyBytes<'a, T, I> {
+        DirtyBytes {
+            values,
+            next: indices.next(),
+            indices,
+        }
+    }
+}
+
+impl<'a, T, I> Iterator for DirtyBytes<'a, T, I> where T: Pod, I: Iterator<Item = usize> {
+    type Item = (usize, &'a [u8]);
+    fn next(&mut self) -> Option<(usize, &'a [u8])> {
+        let start = self.next?;
+        let mut end = start + 1;
+        self.next = None;
+        for i in self.indices.by_ref() {
+            if i != end {
+                self.next = Some(i);
+                break;
+            }
+            end += 1;
+        }
+        let bytes = bytemuck::cast_slice(&self.values[start..end]);
+        Some((start * mem::size_of::<T>(), bytes))
+    }
+}
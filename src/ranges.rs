@@ -0,0 +1,90 @@
+use alloc::vec::Vec;
+use core::cmp;
+use core::ops::Range;
+
+/// Sorted set of ranges where overlapping and touching ranges are coalesced.
+#[derive(Clone, Debug, Default)]
+pub struct RangeSet {
+    ranges: Vec<Range<usize>>,
+}
+
+impl RangeSet {
+    pub fn new() -> RangeSet {
+        RangeSet {
+            ranges: Vec::new(),
+        }
+    }
+
+    pub fn as_slice(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+
+    /// Adds range, merging it with every range it overlaps or touches.
+    pub fn insert(&mut self, range: Range<usize>) {
+        let mut start = range.start;
+        let mut end = range.end;
+        let first = self.ranges.iter().position(|r| r.end >= start).unwrap_or(self.ranges.len());
+        let mut last = first;
+        while last < self.ranges.len() && self.ranges[last].start <= end {
+            start = cmp::min(start, self.ranges[last].start);
+            end = cmp::max(end, self.ranges[last].end);
+            last += 1;
+        }
+        self.ranges.splice(first..last, Some(start..end));
+    }
+
+    /// Accounts for the bytes in range being replaced by new_len bytes,
+    /// shifting the ranges after it and adding the replacement.
+    pub fn splice(&mut self, range: Range<usize>, new_len: usize) {
+        let new_end = range.start + new_len;
+        for r in self.ranges.iter_mut() {
+            if r.end <= range.start {
+                continue;
+            }
+            if r.start >= range.end {
+                *r = (r.start - range.end + new_end)..(r.end - range.end + new_end);
+            } else {
+                let end = match r.end > range.end {
+                    true => r.end - range.end + new_end,
+                    false => new_end,
+                };
+                *r = cmp::min(r.start, range.start)..end;
+            }
+        }
+        self.insert(range.start..new_end);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::RangeSet;
+
+    #[test]
+    fn insert_coalesces() {
+        let mut set = RangeSet::new();
+        set.insert(10..12);
+        set.insert(0..2);
+        set.insert(5..6);
+        assert!(set.as_slice() == [0..2, 5..6, 10..12]);
+        set.insert(2..5);
+        assert!(set.as_slice() == [0..6, 10..12]);
+        set.insert(4..11);
+        assert!(set.as_slice() == [0..12]);
+    }
+
+    #[test]
+    fn splice_shifts_following() {
+        let mut set = RangeSet::new();
+        set.insert(0..1);
+        set.insert(10..12);
+        set.splice(4..6, 5);
+        assert!(set.as_slice() == [0..1, 4..9, 13..15]);
+        set.splice(2..14, 0);
+        assert!(set.as_slice() == [0..1, 2..3]);
+    }
+}
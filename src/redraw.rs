@@ -0,0 +1,80 @@
+use track::Tracked;
+
+/// RedrawTracker answers whether anything needs repainting in a frame of an
+/// immediate mode loop.
+/// Each frame values are checked as they are drawn and explicit requests are
+/// made, e.g. for animations, then take() reports and resets for the next
+/// frame.
+/// A new RedrawTracker requests a redraw so the first frame is drawn.
+#[derive(Clone, Debug)]
+pub struct RedrawTracker {
+    redraw: bool,
+}
+
+impl RedrawTracker {
+    /// Create a new RedrawTracker requesting a redraw.
+    pub const fn new() -> RedrawTracker {
+        RedrawTracker {
+            redraw: true,
+        }
+    }
+
+    /// Requests a redraw if value is dirty.
+    pub fn check(&mut self, value: &dyn Tracked) -> &mut Self {
+        self.redraw |= value.dirty();
+        self
+    }
+
+    /// Requests a redraw.
+    pub fn request(&mut self) {
+        self.redraw = true;
+    }
+
+    /// Returns true if a redraw was requested this frame, false otherwise.
+    pub fn needs_redraw(&self) -> bool {
+        self.redraw
+    }
+
+    /// Returns true if a redraw was requested this frame, resetting for the
+    /// next frame.
+    pub fn take(&mut self) -> bool {
+        let redraw = self.redraw;
+        self.redraw = false;
+        redraw
+    }
+}
+
+impl Default for RedrawTracker {
+    fn default() -> Self {
+        RedrawTracker::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RedrawTracker;
+    use Dirty;
+
+    #[test]
+    fn first_frame_redraws() {
+        let mut redraw = RedrawTracker::new();
+        assert!(redraw.take());
+        assert!(!redraw.take());
+    }
+
+    #[test]
+    fn redraw_when_any_dirty() {
+        let mut redraw = RedrawTracker::new();
+        redraw.take();
+        let mut a = Dirty::new_clean(0);
+        let b = Dirty::new_clean(0);
+        redraw.check(&a).check(&b);
+        assert!(!redraw.needs_redraw());
+        *a += 1;
+        redraw.check(&a).check(&b);
+        assert!(a.render_if_dirty(|v| *v) == Some(1));
+        assert!(redraw.take());
+        redraw.request();
+        assert!(redraw.take());
+    }
+}
@@ -0,0 +1,222 @@
+//! Helpers for structs with Dirty fields producing incremental updates.
+//!
+//! Skip clean fields when serializing and apply received fields as dirty when
+//! deserializing with
+//! `#[serde(default, with = "dirty::serde_helpers", skip_serializing_if = "Dirty::is_clean")]`.
+//!
+//! Dirty itself serializes transparently as its value and deserializes clean,
+//! use persist to keep the dirty flag across a round trip.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use flag::DirtyFlag;
+use Dirty;
+
+/// Serializes the inner value of a Dirty, for use with serialize_with or with.
+pub fn serialize<T, F, S>(dirty: &Dirty<T, F>, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Serialize, S: Serializer
+{
+    dirty.value.serialize(serializer)
+}
+
+/// Deserializes a value into a dirty Dirty, for use with deserialize_with or
+/// with when the input is an update to be applied.
+pub fn deserialize<'de, T, F, D>(deserializer: D) -> Result<Dirty<T, F>, D::Error>
+    where T: Deserialize<'de>, F: DirtyFlag, D: Deserializer<'de>
+{
+    T::deserialize(deserializer).map(Dirty::from)
+}
+
+/// Serializes only the inner value, the dirty flag is not written.
+impl<T, F> Serialize for Dirty<T, F> where T: Serialize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+/// Deserializes the inner value, the Dirty starts clean as its value matches
+/// what was stored.
+impl<'de, T, F> Deserialize<'de> for Dirty<T, F> where T: Deserialize<'de>, F: DirtyFlag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(|val| Dirty::from_flag(val, F::new(false)))
+    }
+}
+
+/// Serializes a Dirty as `{ "value": .., "dirty": .. }` keeping the dirty flag,
+/// for checkpointing unsynced changes, use with
+/// `#[serde(with = "dirty::serde_helpers::persist")]`.
+pub mod persist {
+    use core::fmt;
+    use core::marker::PhantomData;
+    use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
+    use serde::ser::SerializeStruct;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use flag::DirtyFlag;
+    use Dirty;
+
+    const FIELDS: &[&str] = &["value", "dirty"];
+
+    /// Serializes the value and dirty flag of a Dirty.
+    pub fn serialize<T, F, S>(dirty: &Dirty<T, F>, serializer: S) -> Result<S::Ok, S::Error>
+        where T: Serialize, F: DirtyFlag, S: Serializer
+    {
+        let mut state = serializer.serialize_struct("Dirty", 2)?;
+        state.serialize_field("value", dirty.read())?;
+        state.serialize_field("dirty", &dirty.dirty())?;
+        state.end()
+    }
+
+    /// Deserializes the value and dirty flag of a Dirty.
+    pub fn deserialize<'de, T, F, D>(deserializer: D) -> Result<Dirty<T, F>, D::Error>
+        where T: Deserialize<'de>, F: DirtyFlag, D: Deserializer<'de>
+    {
+        deserializer.deserialize_struct("Dirty", FIELDS, PersistVisitor(PhantomData))
+    }
+
+    enum Field {
+        Value,
+        Dirty,
+        Other,
+    }
+
+    impl<'de> Deserialize<'de> for Field {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_identifier(FieldVisitor)
+        }
+    }
+
+    struct FieldVisitor;
+
+    impl<'de> Visitor<'de> for FieldVisitor {
+        type Value = Field;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a field identifier")
+        }
+
+        fn visit_u64<E: de::Error>(self, val: u64) -> Result<Field, E> {
+            Ok(match val {
+                0 => Field::Value,
+                1 => Field::Dirty,
+                _ => Field::Other,
+            })
+        }
+
+        fn visit_str<E: de::Error>(self, val: &str) -> Result<Field, E> {
+            Ok(match val {
+                "value" => Field::Value,
+                "dirty" => Field::Dirty,
+                _ => Field::Other,
+            })
+        }
+    }
+
+    struct PersistVisitor<T, F>(PhantomData<(T, F)>);
+
+    impl<'de, T, F> Visitor<'de> for PersistVisitor<T, F> where T: Deserialize<'de>, F: DirtyFlag {
+        type Value = Dirty<T, F>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a struct with value and dirty fields")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let value = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            let dirty = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            Ok(Dirty::from_flag(value, F::new(dirty)))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut value = None;
+            let mut dirty = None;
+            while let Some(field) = map.next_key()? {
+                match field {
+                    Field::Value => {
+                        if value.is_some() {
+                            return Err(de::Error::duplicate_field("value"));
+                        }
+                        value = Some(map.next_value()?);
+                    },
+                    Field::Dirty => {
+                        if dirty.is_some() {
+                            return Err(de::Error::duplicate_field("dirty"));
+                        }
+                        dirty = Some(map.next_value()?);
+                    },
+                    Field::Other => {
+                        map.next_value::<IgnoredAny>()?;
+                    },
+                }
+            }
+            let value = value.ok_or_else(|| de::Error::missing_field("value"))?;
+            let dirty = dirty.ok_or_else(|| de::Error::missing_field("dirty"))?;
+            Ok(Dirty::from_flag(value, F::new(dirty)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use serde_json;
+    use std::string::String;
+    use Dirty;
+
+    #[derive(Serialize, Deserialize, Default)]
+    struct State {
+        #[serde(default, with = "::serde_helpers", skip_serializing_if = "Dirty::is_clean")]
+        name: Dirty<String>,
+        #[serde(default, with = "::serde_helpers", skip_serializing_if = "Dirty::is_clean")]
+        count: Dirty<u32>,
+    }
+
+    #[test]
+    fn serialize_transparent() {
+        let dirty = Dirty::new([1, 2]);
+        assert!(serde_json::to_string(&dirty).unwrap() == "[1,2]");
+    }
+
+    #[test]
+    fn deserialize_clean() {
+        let dirty: Dirty<u32> = serde_json::from_str("5").unwrap();
+        assert!(!dirty.dirty());
+        assert!(*dirty == 5);
+    }
+
+    #[test]
+    fn skip_clean_fields() {
+        let mut state = State::default();
+        state.name.clear();
+        state.count.clear();
+        assert!(serde_json::to_string(&state).unwrap() == "{}");
+        *state.count.write() = 3;
+        assert!(serde_json::to_string(&state).unwrap() == r#"{"count":3}"#);
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Checkpoint {
+        #[serde(with = "::serde_helpers::persist")]
+        pending: Dirty<u32>,
+    }
+
+    #[test]
+    fn persist_keeps_flag() {
+        let checkpoint = Checkpoint { pending: Dirty::new(7) };
+        let json = serde_json::to_string(&checkpoint).unwrap();
+        assert!(json == r#"{"pending":{"value":7,"dirty":true}}"#);
+        let restored: Checkpoint = serde_json::from_str(&json).unwrap();
+        assert!(restored.pending.dirty() && *restored.pending == 7);
+        let clean: Checkpoint = serde_json::from_str(r#"{"pending":{"dirty":false,"value":1}}"#).unwrap();
+        assert!(clean.pending.is_clean());
+        assert!(serde_json::from_str::<Checkpoint>(r#"{"pending":{"value":1}}"#).is_err());
+    }
+
+    #[test]
+    fn deserialize_update_dirty() {
+        let state: State = serde_json::from_str(r#"{"name":"a"}"#).unwrap();
+        assert!(state.name.dirty());
+        assert!(*state.name == "a");
+        assert!(*state.count == 0);
+    }
+}
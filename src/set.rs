@@ -0,0 +1,155 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::ops::Deref;
+use std::vec::Vec;
+
+use change::{KeyChange, State};
+
+/// DirtySet wraps a `HashSet<T>` recording which values were added or removed
+/// since the last clear().
+/// Use deref (dirty_set.contains(v)) to read the inner set.
+pub struct DirtySet<T> {
+    set: HashSet<T>,
+    changes: HashMap<T, State>,
+}
+
+impl<T> DirtySet<T> where T: Eq + Hash + Clone {
+    /// Create a new empty DirtySet.
+    pub fn new() -> DirtySet<T> {
+        DirtySet {
+            set: HashSet::new(),
+            changes: HashMap::new(),
+        }
+    }
+
+    /// Returns true if any value was added or removed since the last clear(),
+    /// false otherwise.
+    pub fn dirty(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// Adds a value, recording it as added unless it was removed since the
+    /// last clear(). Returns true if the value was not present.
+    pub fn insert(&mut self, val: T) -> bool {
+        if !self.set.insert(val.clone()) {
+            return false;
+        }
+        if self.changes.remove(&val) != Some(State::Removed) {
+            self.changes.insert(val, State::Inserted);
+        }
+        true
+    }
+
+    /// Removes a value, recording it as removed unless it was added since the
+    /// last clear(). Returns true if the value was present.
+    pub fn remove<Q>(&mut self, val: &Q) -> bool
+        where T: Borrow<Q>, Q: ?Sized + Eq + Hash
+    {
+        let val = match self.set.take(val) {
+            Some(val) => val,
+            None => return false,
+        };
+        if self.changes.remove::<T>(&val) != Some(State::Inserted) {
+            self.changes.insert(val, State::Removed);
+        }
+        true
+    }
+
+    /// Iterate over the values added since the last clear().
+    pub fn added(&self) -> impl Iterator<Item = &T> {
+        self.changes.iter()
+            .filter(|&(_, s)| *s == State::Inserted)
+            .map(|(v, _)| v)
+    }
+
+    /// Iterate over the values removed since the last clear().
+    pub fn removed(&self) -> impl Iterator<Item = &T> {
+        self.changes.iter()
+            .filter(|&(_, s)| *s == State::Removed)
+            .map(|(v, _)| v)
+    }
+
+    /// Returns every change since the last clear() and clears them, added
+    /// values are reported as inserted.
+    pub fn drain_changes(&mut self) -> Vec<KeyChange<T>> {
+        self.changes.drain()
+            .map(|(v, s)| s.into_change(v))
+            .collect()
+    }
+
+    /// Clears the recorded changes.
+    pub fn clear(&mut self) {
+        self.changes.clear();
+    }
+
+    /// Consumes the DirtySet, returning the inner HashSet.
+    pub fn into_inner(self) -> HashSet<T> {
+        self.set
+    }
+}
+
+impl<T> From<HashSet<T>> for DirtySet<T> where T: Eq + Hash + Clone {
+    /// Create a new DirtySet with every value recorded as added.
+    fn from(set: HashSet<T>) -> DirtySet<T> {
+        DirtySet {
+            changes: set.iter().map(|v| (v.clone(), State::Inserted)).collect(),
+            set,
+        }
+    }
+}
+
+impl<T> Deref for DirtySet<T> {
+    type Target = HashSet<T>;
+    fn deref(&self) -> &HashSet<T> {
+        &self.set
+    }
+}
+
+impl<T> Default for DirtySet<T> where T: Eq + Hash + Clone {
+    fn default() -> Self {
+        DirtySet::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtySet;
+    use change::KeyChange;
+    use std::vec::Vec;
+
+    fn clean() -> DirtySet<&'static str> {
+        let mut set = DirtySet::new();
+        set.insert("read");
+        set.insert("write");
+        set.clear();
+        set
+    }
+
+    #[test]
+    fn records_added_and_removed() {
+        let mut set = clean();
+        assert!(!set.dirty());
+        assert!(set.insert("admin"));
+        assert!(!set.insert("read"));
+        assert!(set.remove("write"));
+        assert!(!set.remove("write"));
+        assert!(set.added().collect::<Vec<_>>() == vec![&"admin"]);
+        assert!(set.removed().collect::<Vec<_>>() == vec![&"write"]);
+        assert!(set.contains("admin") && !set.contains("write"));
+        set.clear();
+        assert!(!set.dirty());
+    }
+
+    #[test]
+    fn add_then_remove_is_no_change() {
+        let mut set = clean();
+        set.insert("admin");
+        set.remove("admin");
+        set.remove("read");
+        set.insert("read");
+        assert!(!set.dirty());
+        set.remove("write");
+        assert!(set.drain_changes() == vec![KeyChange::Removed("write")]);
+    }
+}
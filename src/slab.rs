@@ -0,0 +1,220 @@
+use alloc::vec::Vec;
+use core::mem;
+
+/// Handle to a value stored in a DirtySlab.
+/// A key is invalidated when its value is removed, even if the slot is reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SlabKey {
+    index: u32,
+    generation: u32,
+}
+
+impl SlabKey {
+    /// Returns the slot index of the key.
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+}
+
+struct Slot<T> {
+    value: Option<T>,
+    generation: u32,
+    dirty: bool,
+}
+
+/// DirtySlab stores values of type T behind SlabKey handles with a dirty flag
+/// per value, set when the value is inserted or written through its key.
+/// Removed keys are recorded until drain_removed().
+pub struct DirtySlab<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    removed: Vec<SlabKey>,
+    len: usize,
+}
+
+impl<T> DirtySlab<T> {
+    /// Create a new empty DirtySlab.
+    pub fn new() -> DirtySlab<T> {
+        DirtySlab {
+            slots: Vec::new(),
+            free: Vec::new(),
+            removed: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no values, false otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a value, which starts dirty, returning its key.
+    pub fn insert(&mut self, val: T) -> SlabKey {
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.value = Some(val);
+                slot.dirty = true;
+                SlabKey {
+                    index,
+                    generation: slot.generation,
+                }
+            },
+            None => {
+                let index = self.slots.len() as u32;
+                self.slots.push(Slot {
+                    value: Some(val),
+                    generation: 0,
+                    dirty: true,
+                });
+                SlabKey {
+                    index,
+                    generation: 0,
+                }
+            },
+        }
+    }
+
+    /// Removes the value of key, recording the key as removed.
+    pub fn remove(&mut self, key: SlabKey) -> Option<T> {
+        let slot = self.slot_mut(key)?;
+        let val = slot.value.take();
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.dirty = false;
+        self.free.push(key.index);
+        self.removed.push(key);
+        self.len -= 1;
+        val
+    }
+
+    /// Returns true if key refers to a value, false otherwise.
+    pub fn contains(&self, key: SlabKey) -> bool {
+        self.slot(key).is_some()
+    }
+
+    /// Returns true if the value of key is dirty, false otherwise.
+    pub fn dirty(&self, key: SlabKey) -> bool {
+        self.slot(key).map(|s| s.dirty).unwrap_or(false)
+    }
+
+    /// Returns true if any value is dirty or any key was removed, false
+    /// otherwise.
+    pub fn any_dirty(&self) -> bool {
+        !self.removed.is_empty() || self.slots.iter().any(|s| s.dirty)
+    }
+
+    /// Read the value of key.
+    pub fn get(&self, key: SlabKey) -> Option<&T> {
+        self.slot(key)?.value.as_ref()
+    }
+
+    /// Writable value return, sets the dirty flag of the value.
+    pub fn get_mut(&mut self, key: SlabKey) -> Option<&mut T> {
+        let slot = self.slot_mut(key)?;
+        slot.dirty = true;
+        slot.value.as_mut()
+    }
+
+    /// Iterate over the values and their keys.
+    pub fn iter(&self) -> impl Iterator<Item = (SlabKey, &T)> {
+        self.slots.iter().enumerate().filter_map(|(i, slot)| {
+            let key = SlabKey {
+                index: i as u32,
+                generation: slot.generation,
+            };
+            slot.value.as_ref().map(|val| (key, val))
+        })
+    }
+
+    /// Iterate over the dirty values and their keys, clearing the dirty flag
+    /// of each value as it is yielded.
+    pub fn drain_dirty(&mut self) -> impl Iterator<Item = (SlabKey, &T)> {
+        self.slots.iter_mut().enumerate().filter_map(|(i, slot)| {
+            if !mem::replace(&mut slot.dirty, false) {
+                return None;
+            }
+            let key = SlabKey {
+                index: i as u32,
+                generation: slot.generation,
+            };
+            slot.value.as_ref().map(|val| (key, val))
+        })
+    }
+
+    /// Returns the keys removed since the last drain_removed() or clear_all().
+    pub fn drain_removed(&mut self) -> Vec<SlabKey> {
+        mem::take(&mut self.removed)
+    }
+
+    /// Clears the dirty flag of the value of key.
+    pub fn clear(&mut self, key: SlabKey) {
+        if let Some(slot) = self.slot_mut(key) {
+            slot.dirty = false;
+        }
+    }
+
+    /// Clears the dirty flag of every value and the removed keys.
+    pub fn clear_all(&mut self) {
+        for slot in self.slots.iter_mut() {
+            slot.dirty = false;
+        }
+        self.removed.clear();
+    }
+
+    fn slot(&self, key: SlabKey) -> Option<&Slot<T>> {
+        self.slots.get(key.index as usize)
+            .filter(|s| s.generation == key.generation && s.value.is_some())
+    }
+
+    fn slot_mut(&mut self, key: SlabKey) -> Option<&mut Slot<T>> {
+        self.slots.get_mut(key.index as usize)
+            .filter(|s| s.generation == key.generation && s.value.is_some())
+    }
+}
+
+impl<T> Default for DirtySlab<T> {
+    fn default() -> Self {
+        DirtySlab::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtySlab;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn drain_dirty_clears() {
+        let mut slab = DirtySlab::new();
+        let a = slab.insert(1);
+        let b = slab.insert(2);
+        assert!(slab.drain_dirty().count() == 2);
+        assert!(!slab.any_dirty());
+        *slab.get_mut(b).unwrap() += 1;
+        assert!(slab.dirty(b) && !slab.dirty(a));
+        assert!(slab.drain_dirty().collect::<Vec<_>>() == vec![(b, &3)]);
+        assert!(slab.drain_dirty().next().is_none());
+    }
+
+    #[test]
+    fn removed_keys_invalidated() {
+        let mut slab = DirtySlab::new();
+        let a = slab.insert(1);
+        slab.clear_all();
+        assert!(slab.remove(a) == Some(1));
+        assert!(slab.remove(a).is_none());
+        let b = slab.insert(2);
+        assert!(b.index() == a.index());
+        assert!(slab.get(a).is_none());
+        assert!(slab.get_mut(a).is_none());
+        assert!(slab.drain_removed() == vec![a]);
+        assert!(slab.iter().collect::<Vec<_>>() == vec![(b, &2)]);
+        assert!(slab.len() == 1);
+    }
+}
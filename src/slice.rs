@@ -0,0 +1,241 @@
+#[cfg(feature = "bytemuck")]
+use bytemuck::Pod;
+use alloc::vec::Vec;
+use core::ops::{Deref, Index, IndexMut};
+
+use bits::{self, SetIndices};
+#[cfg(feature = "bytemuck")]
+use pod::DirtyBytes;
+
+/// DirtySlice stores values of type T contiguously with their dirty flags
+/// packed in a separate bitset, so scanning millions of elements skips clean
+/// runs a word at a time and clear_all() is a memset.
+/// Use deref (`dirty_slice[i]`) to read elements, a mutable index marks the
+/// element dirty.
+pub struct DirtySlice<T> {
+    values: Vec<T>,
+    bits: Vec<u64>,
+}
+
+impl<T> DirtySlice<T> {
+    /// Create a new empty DirtySlice.
+    pub fn new() -> DirtySlice<T> {
+        DirtySlice {
+            values: Vec::new(),
+            bits: Vec::new(),
+        }
+    }
+
+    /// Create a new DirtySlice with every element clean.
+    pub fn from_clean(values: Vec<T>) -> DirtySlice<T> {
+        DirtySlice {
+            bits: vec![0; values.len().div_ceil(64)],
+            values,
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if there are no elements, false otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns true if the element at index is dirty, false otherwise.
+    pub fn dirty(&self, index: usize) -> bool {
+        index < self.values.len() && self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Returns true if any element is dirty, false otherwise.
+    pub fn any_dirty(&self) -> bool {
+        self.bits.iter().any(|&w| w != 0)
+    }
+
+    /// Marks the element at index dirty.
+    pub fn mark(&mut self, index: usize) {
+        assert!(index < self.values.len(), "index out of bounds");
+        self.bits[index / 64] |= 1 << (index % 64);
+    }
+
+    /// Writable element return, sets the element's dirty flag.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.values.len() {
+            return None;
+        }
+        self.mark(index);
+        self.values.get_mut(index)
+    }
+
+    /// Sets the element at index, marking it dirty.
+    pub fn set(&mut self, index: usize, val: T) {
+        self.values[index] = val;
+        self.mark(index);
+    }
+
+    /// Appends an element, which starts dirty.
+    pub fn push(&mut self, val: T) {
+        if self.values.len().is_multiple_of(64) {
+            self.bits.push(0);
+        }
+        self.values.push(val);
+        self.mark(self.values.len() - 1);
+    }
+
+    /// Clears the dirty flag of the element at index.
+    pub fn clear(&mut self, index: usize) {
+        if index < self.values.len() {
+            self.bits[index / 64] &= !(1 << (index % 64));
+        }
+    }
+
+    /// Clears the dirty flag of every element.
+    pub fn clear_all(&mut self) {
+        self.bits.fill(0);
+    }
+
+    /// Iterate over the indices of the dirty elements in increasing order.
+    pub fn dirty_indices(&self) -> SetIndices<'_> {
+        SetIndices::new(&self.bits)
+    }
+
+    /// Returns the index of the first dirty element at or after from.
+    pub fn find_next_dirty(&self, from: usize) -> Option<usize> {
+        bits::find_next(&self.bits, from)
+    }
+
+    /// Returns the number of dirty elements.
+    pub fn count_dirty(&self) -> usize {
+        bits::count(&self.bits)
+    }
+
+    /// Iterate over the dirty elements and their indices.
+    pub fn iter_dirty(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.dirty_indices().map(move |i| (i, &self.values[i]))
+    }
+
+    /// Returns the elements as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Consumes the DirtySlice, returning the inner Vec.
+    pub fn into_inner(self) -> Vec<T> {
+        self.values
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<T> DirtySlice<T> where T: Pod {
+    /// Iterate over the byte offsets and bytes of each run of consecutive
+    /// dirty elements.
+    pub fn dirty_bytes(&self) -> impl Iterator<Item = (usize, &[u8])> {
+        DirtyBytes::new(&self.values, self.dirty_indices())
+    }
+}
+
+impl<T> From<Vec<T>> for DirtySlice<T> {
+    /// Create a new DirtySlice with every element dirty.
+    fn from(values: Vec<T>) -> DirtySlice<T> {
+        let len = values.len();
+        let mut bits = vec![!0; len.div_ceil(64)];
+        if !len.is_multiple_of(64) {
+            if let Some(last) = bits.last_mut() {
+                *last = (1 << (len % 64)) - 1;
+            }
+        }
+        DirtySlice { values, bits }
+    }
+}
+
+impl<T> Deref for DirtySlice<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        &self.values
+    }
+}
+
+impl<T> Index<usize> for DirtySlice<T> {
+    type Output = T;
+    fn index(&self, index: usize) -> &T {
+        &self.values[index]
+    }
+}
+
+/// Like get_mut() this sets the element's dirty flag.
+impl<T> IndexMut<usize> for DirtySlice<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.mark(index);
+        &mut self.values[index]
+    }
+}
+
+impl<T> Default for DirtySlice<T> {
+    fn default() -> Self {
+        DirtySlice::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtySlice;
+    use std::vec::Vec;
+
+    #[test]
+    fn from_vec_all_dirty() {
+        let slice = DirtySlice::from((0..70).collect::<Vec<u32>>());
+        assert!(slice.dirty(69) && !slice.dirty(70));
+        assert!(slice.dirty_indices().count() == 70);
+    }
+
+    #[test]
+    fn iter_dirty_skips_clean() {
+        let mut slice = DirtySlice::from_clean(vec![0u32; 1000]);
+        assert!(!slice.any_dirty());
+        slice[3] = 1;
+        slice.set(640, 2);
+        *slice.get_mut(999).unwrap() = 3;
+        assert!(slice.get_mut(1000).is_none());
+        let dirty: Vec<(usize, u32)> = slice.iter_dirty().map(|(i, &v)| (i, v)).collect();
+        assert!(dirty == [(3, 1), (640, 2), (999, 3)]);
+        assert!(slice.count_dirty() == 3);
+        assert!(slice.find_next_dirty(4) == Some(640));
+        slice.clear(640);
+        assert!(slice.find_next_dirty(4) == Some(999));
+        assert!(slice.dirty_indices().collect::<Vec<usize>>() == [3, 999]);
+        slice.clear_all();
+        assert!(!slice.any_dirty() && slice[999] == 3);
+    }
+
+    #[test]
+    fn push_marks() {
+        let mut slice = DirtySlice::new();
+        for i in 0..65 {
+            slice.push(i);
+        }
+        slice.clear_all();
+        slice.push(65);
+        assert!(slice.len() == 66);
+        assert!(slice.dirty_indices().collect::<Vec<usize>>() == [65]);
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn dirty_bytes_coalesces_runs() {
+        let mut slice = DirtySlice::from_clean(vec![0u32; 130]);
+        slice.set(1, 1);
+        slice.set(2, 2);
+        slice.set(63, 3);
+        slice.set(64, 4);
+        slice.set(129, 5);
+        let runs: Vec<(usize, &[u8])> = slice.dirty_bytes().collect();
+        assert!(runs.len() == 3);
+        assert!(runs[0].0 == 4 && runs[0].1 == bytemuck::cast_slice::<u32, u8>(&[1, 2]));
+        assert!(runs[1].0 == 63 * 4 && runs[1].1.len() == 8);
+        assert!(runs[2].0 == 129 * 4 && runs[2].1 == 5u32.to_ne_bytes());
+        slice.clear_all();
+        assert!(slice.dirty_bytes().next().is_none());
+    }
+}
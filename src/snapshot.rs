@@ -0,0 +1,188 @@
+use core::fmt;
+use core::ops::Deref;
+
+/// SnapshotDirty wraps a value of type T like Dirty and keeps a copy of the
+/// value as it was at the last clear(), so consumers may compare the old and
+/// new values when computing deltas.
+/// A new SnapshotDirty starts dirty with a snapshot equal to its value.
+pub struct SnapshotDirty<T> where T: Clone {
+    value: T,
+    snapshot: T,
+    dirty: bool,
+}
+
+impl<T> SnapshotDirty<T> where T: Clone {
+    /// Create a new SnapshotDirty.
+    pub fn new(val: T) -> SnapshotDirty<T> {
+        SnapshotDirty {
+            snapshot: val.clone(),
+            value: val,
+            dirty: true,
+        }
+    }
+
+    /// Create a new clean SnapshotDirty.
+    pub fn new_clean(val: T) -> SnapshotDirty<T> {
+        SnapshotDirty {
+            snapshot: val.clone(),
+            value: val,
+            dirty: false,
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.value
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.dirty = true;
+    }
+
+    /// Run f with the writable value, sets the dirty flag only if it returns
+    /// Ok. On error the value and dirty flag are restored to what they were
+    /// before f ran.
+    pub fn try_update<E, F>(&mut self, f: F) -> Result<(), E>
+        where F: FnOnce(&mut T) -> Result<(), E>
+    {
+        let old = match self.dirty {
+            true => Some(self.value.clone()),
+            false => None,
+        };
+        match f(&mut self.value) {
+            Ok(()) => {
+                self.dirty = true;
+                Ok(())
+            },
+            Err(err) => {
+                match old {
+                    Some(old) => self.value = old,
+                    // A clean value equals the snapshot, no copy needed.
+                    None => self.value.clone_from(&self.snapshot),
+                }
+                Err(err)
+            },
+        }
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Read the value as it was at the last clear().
+    pub fn snapshot(&self) -> &T {
+        &self.snapshot
+    }
+
+    /// Clears the dirty flag, taking a new snapshot of the value.
+    pub fn clear(&mut self) {
+        self.snapshot.clone_from(&self.value);
+        self.dirty = false;
+    }
+
+    /// Restores the value to the snapshot taken at the last clear(), clearing
+    /// the dirty flag.
+    pub fn revert(&mut self) {
+        self.value.clone_from(&self.snapshot);
+        self.dirty = false;
+    }
+
+    /// Read the snapshot and current value only if modified since the last
+    /// clear().
+    pub fn diff(&self) -> Option<(&T, &T)> {
+        match self.dirty {
+            true => Some((&self.snapshot, &self.value)),
+            false => None,
+        }
+    }
+
+    /// Consumes the SnapshotDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for SnapshotDirty<T> where T: Clone {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Default for SnapshotDirty<T> where T: Clone + Default {
+    fn default() -> Self {
+        SnapshotDirty::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for SnapshotDirty<T> where T: Clone + fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SnapshotDirty")
+            .field("value", &self.value)
+            .field("snapshot", &self.snapshot)
+            .field("dirty", &self.dirty)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SnapshotDirty;
+
+    #[test]
+    fn new_clean() {
+        let dirty = SnapshotDirty::new_clean(0);
+        assert!(!dirty.dirty());
+    }
+
+    #[test]
+    fn new_dirty() {
+        let dirty = SnapshotDirty::new(1);
+        assert!(dirty.dirty());
+        assert!(dirty.diff() == Some((&1, &1)));
+    }
+
+    #[test]
+    fn diff_against_last_clear() {
+        let mut dirty = SnapshotDirty::new(1);
+        dirty.clear();
+        assert!(dirty.diff().is_none());
+        *dirty.write() += 1;
+        dirty.set(5);
+        assert!(dirty.diff() == Some((&1, &5)));
+        dirty.clear();
+        assert!(*dirty.snapshot() == 5);
+        assert!(dirty.diff().is_none());
+    }
+
+    #[test]
+    fn revert_restores_snapshot() {
+        let mut dirty = SnapshotDirty::new(1);
+        dirty.clear();
+        dirty.set(2);
+        dirty.revert();
+        assert!(!dirty.dirty());
+        assert!(*dirty == 1);
+    }
+
+    #[test]
+    fn try_update_rolls_back() {
+        let mut dirty = SnapshotDirty::new_clean(1);
+        assert!(dirty.try_update(|v| { *v = 5; Err(()) }) == Err(()));
+        assert!(!dirty.dirty() && *dirty == 1);
+        dirty.set(2);
+        assert!(dirty.try_update(|v| { *v = 5; Err(()) }) == Err(()));
+        assert!(dirty.diff() == Some((&1, &2)));
+        assert!(dirty.try_update(|v| -> Result<(), ()> { *v = 3; Ok(()) }) == Ok(()));
+        assert!(*dirty == 3);
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::sync::{Arc, PoisonError};
+
+use sync::{DirtyMutex, DirtyMutexGuard};
+
+/// Splits val into a writer and a reader handle which may be sent to different
+/// threads. The pair starts dirty.
+/// Locks are held only while the writer's guard is alive or the reader clones
+/// the value, and a panic while writing does not poison the handles.
+pub fn split<T>(val: T) -> (WriteHandle<T>, ReadHandle<T>) {
+    let shared = Arc::new(DirtyMutex::new(val));
+    (WriteHandle { shared: shared.clone() }, ReadHandle { shared })
+}
+
+/// WriteHandle is the writing half of a split(), its guard sets the dirty
+/// flag seen by the ReadHandle.
+pub struct WriteHandle<T> {
+    shared: Arc<DirtyMutex<T>>,
+}
+
+impl<T> WriteHandle<T> {
+    /// Locks the value for writing, the dirty flag is set on drop if the
+    /// guard was mutably dereferenced.
+    pub fn write(&mut self) -> DirtyMutexGuard<'_, T> {
+        self.shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        *self.write() = val;
+    }
+}
+
+/// ReadHandle is the reading half of a split().
+pub struct ReadHandle<T> {
+    shared: Arc<DirtyMutex<T>>,
+}
+
+impl<T> ReadHandle<T> {
+    /// Returns true if written since the last load_dirty(), false otherwise.
+    /// Does not lock.
+    pub fn poll_dirty(&self) -> bool {
+        self.shared.dirty()
+    }
+
+    /// Clones the value, leaving the dirty flag as is.
+    pub fn load(&self) -> T where T: Clone {
+        self.shared.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Clones the value only if written since the last load_dirty(), clearing
+    /// the dirty flag. Does not lock when clean.
+    pub fn load_dirty(&mut self) -> Option<T> where T: Clone {
+        let guard = self.shared.lock_if_dirty().unwrap_or_else(PoisonError::into_inner);
+        guard.map(|val| val.clone())
+    }
+
+    /// Runs f with the value only if written since the last load_dirty(),
+    /// clearing the dirty flag, for values too large to clone. The writer
+    /// is blocked while f runs.
+    pub fn read_dirty<R, F>(&mut self, f: F) -> Option<R> where F: FnOnce(&T) -> R {
+        let guard = self.shared.lock_if_dirty().unwrap_or_else(PoisonError::into_inner);
+        guard.map(|val| f(&val))
+    }
+}
+
+impl<T> fmt::Debug for WriteHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WriteHandle")
+            .field("dirty", &self.shared.dirty())
+            .finish()
+    }
+}
+
+impl<T> fmt::Debug for ReadHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReadHandle")
+            .field("dirty", &self.shared.dirty())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn handoff_between_threads() {
+        let (mut writer, mut reader) = split(0u32);
+        assert!(reader.load_dirty() == Some(0));
+        assert!(!reader.poll_dirty());
+        let (done, finished) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            for i in 1..=10 {
+                *writer.write() = i;
+            }
+            done.send(()).unwrap();
+            writer
+        });
+        finished.recv().unwrap();
+        assert!(reader.poll_dirty());
+        assert!(reader.read_dirty(|val| *val * 2) == Some(20));
+        assert!(reader.load_dirty().is_none());
+        let mut writer = handle.join().unwrap();
+        {
+            let guard = writer.write();
+            assert!(*guard == 10);
+        }
+        assert!(!reader.poll_dirty());
+        writer.set(11);
+        assert!(reader.load() == 11 && reader.poll_dirty());
+    }
+}
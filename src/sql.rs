@@ -0,0 +1,39 @@
+use serde::Serialize;
+use serde_json::{self, Value};
+use std::fmt::Write;
+use std::string::String;
+use std::vec::Vec;
+
+use fields::StructFields;
+use patch::{dirty_members, to_object};
+
+/// SqlUpdate turns the dirty fields of a StructFields tracker into the SET
+/// clause of a SQL UPDATE, such as `name = $1, email = $2`, with the
+/// serialized field values as the parameters, so an update writes only the
+/// changed columns.
+/// Columns are named as in FIELDS, so serde renames are not followed. A dirty
+/// field missing from the serialized struct, such as a skipped None, is bound
+/// as null.
+pub trait SqlUpdate: StructFields where Self::Inner: Serialize {
+    /// Returns the SET clause of the dirty columns and their parameters, with
+    /// placeholders numbered from first, or None if no field is dirty.
+    fn sql_update(&self, first: usize) -> serde_json::Result<Option<(String, Vec<Value>)>> {
+        let mut new = to_object(self.inner())?;
+        let mut set = String::new();
+        let mut params = Vec::new();
+        for name in dirty_members(self) {
+            if !params.is_empty() {
+                set.push_str(", ");
+            }
+            // Writing to a String can not fail.
+            let _ = write!(set, "{} = ${}", name, first + params.len());
+            params.push(new.remove(name).unwrap_or(Value::Null));
+        }
+        match params.is_empty() {
+            true => Ok(None),
+            false => Ok(Some((set, params))),
+        }
+    }
+}
+
+impl<F> SqlUpdate for F where F: StructFields, F::Inner: Serialize {}
@@ -0,0 +1,152 @@
+use core::fmt;
+use core::ops::{Add, Deref};
+
+/// Stats summarizes the values written to a StatDirty between clears.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats<T> {
+    /// Smallest value written.
+    pub min: T,
+    /// Largest value written.
+    pub max: T,
+    /// Sum of the values written.
+    pub sum: T,
+    /// Number of values written.
+    pub count: usize,
+}
+
+/// StatDirty wraps a numeric value of type T like Dirty and tracks the min,
+/// max and sum of the values written between clears, so peaks are not lost
+/// when sampling at a lower rate than writes happen.
+/// Values are recorded by set() and update(), a new StatDirty starts dirty
+/// with no values recorded.
+pub struct StatDirty<T> where T: Copy + PartialOrd + Add<Output = T> {
+    value: T,
+    dirty: bool,
+    stats: Option<Stats<T>>,
+}
+
+impl<T> StatDirty<T> where T: Copy + PartialOrd + Add<Output = T> {
+    /// Create a new StatDirty.
+    pub fn new(val: T) -> StatDirty<T> {
+        StatDirty {
+            value: val,
+            dirty: true,
+            stats: None,
+        }
+    }
+
+    /// Create a new clean StatDirty.
+    pub fn new_clean(val: T) -> StatDirty<T> {
+        StatDirty {
+            value: val,
+            dirty: false,
+            stats: None,
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Set the value, sets the dirty flag and records the value.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.dirty = true;
+        self.stats = Some(match self.stats {
+            Some(stats) => Stats {
+                min: if val < stats.min { val } else { stats.min },
+                max: if val > stats.max { val } else { stats.max },
+                sum: stats.sum + val,
+                count: stats.count + 1,
+            },
+            None => Stats {
+                min: val,
+                max: val,
+                sum: val,
+                count: 1,
+            },
+        });
+    }
+
+    /// Set the value to the result of f, sets the dirty flag and records the
+    /// value.
+    pub fn update<F>(&mut self, f: F) where F: FnOnce(T) -> T {
+        let val = f(self.value);
+        self.set(val);
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the stats of the values recorded since the last clear(), or
+    /// None if there are none.
+    pub fn stats(&self) -> Option<Stats<T>> {
+        self.stats
+    }
+
+    /// Clears the dirty flag and the recorded values.
+    pub fn clear(&mut self) {
+        self.dirty = false;
+        self.stats = None;
+    }
+
+    /// Returns the stats only if values were recorded since the last clear(),
+    /// clearing the dirty flag and the recorded values.
+    pub fn take_stats(&mut self) -> Option<Stats<T>> {
+        let stats = self.stats.take();
+        self.dirty = false;
+        stats
+    }
+
+    /// Consumes the StatDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for StatDirty<T> where T: Copy + PartialOrd + Add<Output = T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Default for StatDirty<T> where T: Copy + PartialOrd + Add<Output = T> + Default {
+    fn default() -> Self {
+        StatDirty::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for StatDirty<T> where T: Copy + PartialOrd + Add<Output = T> + fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StatDirty")
+            .field("value", &self.value)
+            .field("dirty", &self.dirty)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StatDirty, Stats};
+
+    #[test]
+    fn peaks_between_samples() {
+        let mut latency = StatDirty::new_clean(0u32);
+        assert!(latency.take_stats().is_none());
+        latency.set(5);
+        latency.set(40);
+        latency.update(|v| v - 38);
+        assert!(*latency == 2);
+        assert!(latency.take_stats() == Some(Stats { min: 2, max: 40, sum: 47, count: 3 }));
+        assert!(!latency.dirty() && latency.stats().is_none());
+        latency.set(7);
+        assert!(latency.stats().map(|s| s.max) == Some(7));
+        latency.clear();
+        assert!(latency.stats().is_none());
+    }
+}
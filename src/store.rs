@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+use std::string::ToString;
+use std::vec::Vec;
+
+use Dirty;
+
+/// Backend is the persistent storage behind a DirtyStore.
+pub trait Backend<K, V> {
+    /// The error of a failed load, store or remove.
+    type Error;
+
+    /// Loads the value for key, returning None if there is no value.
+    fn load(&mut self, key: &K) -> Result<Option<V>, Self::Error>;
+
+    /// Stores the value for key.
+    fn store(&mut self, key: &K, val: &V) -> Result<(), Self::Error>;
+
+    /// Removes the value for key.
+    fn remove(&mut self, key: &K) -> Result<(), Self::Error>;
+}
+
+/// MemoryBackend stores values in a HashMap, for tests and as a stand in for
+/// a real backend.
+#[derive(Debug, Default)]
+pub struct MemoryBackend<K, V> {
+    map: HashMap<K, V>,
+}
+
+impl<K, V> MemoryBackend<K, V> where K: Eq + Hash {
+    /// Create a new empty MemoryBackend.
+    pub fn new() -> MemoryBackend<K, V> {
+        MemoryBackend {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Read the stored values.
+    pub fn as_map(&self) -> &HashMap<K, V> {
+        &self.map
+    }
+}
+
+impl<K, V> From<HashMap<K, V>> for MemoryBackend<K, V> {
+    fn from(map: HashMap<K, V>) -> MemoryBackend<K, V> {
+        MemoryBackend { map }
+    }
+}
+
+impl<K, V> Backend<K, V> for MemoryBackend<K, V> where K: Eq + Hash + Clone, V: Clone {
+    type Error = ();
+
+    fn load(&mut self, key: &K) -> Result<Option<V>, ()> {
+        Ok(self.map.get(key).cloned())
+    }
+
+    fn store(&mut self, key: &K, val: &V) -> Result<(), ()> {
+        self.map.insert(key.clone(), val.clone());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &K) -> Result<(), ()> {
+        self.map.remove(key);
+        Ok(())
+    }
+}
+
+/// FsBackend stores the bytes of each value in a file of a directory named by
+/// the key's Display output. A key naming anything but a single file in the
+/// directory, such as "../x" or "/etc/passwd", fails with InvalidInput, as
+/// does a key starting with a '.' which is kept for temporary files.
+/// Values are written to a temporary file and renamed over the old file, so a
+/// failed store leaves the old value in place.
+#[derive(Debug)]
+pub struct FsBackend {
+    dir: PathBuf,
+}
+
+impl FsBackend {
+    /// Create a new FsBackend storing files in dir, creating it if needed.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> io::Result<FsBackend> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(FsBackend { dir })
+    }
+
+    /// Returns the path of the file for key, or an InvalidInput error if the
+    /// key is not a plain file name or starts with a '.'.
+    pub fn path<K: fmt::Display>(&self, key: &K) -> io::Result<PathBuf> {
+        let name = key.to_string();
+        let mut components = Path::new(&name).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(file)), None) if file == name.as_str() && !name.starts_with('.') => {
+                Ok(self.dir.join(file))
+            },
+            _ => Err(io::Error::new(ErrorKind::InvalidInput, "key is not a plain file name")),
+        }
+    }
+
+    // A dot file, which no key maps to.
+    fn tmp_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!(".{}.tmp", key))
+    }
+}
+
+impl<K, V> Backend<K, V> for FsBackend where K: fmt::Display, V: AsRef<[u8]> + From<Vec<u8>> {
+    type Error = io::Error;
+
+    fn load(&mut self, key: &K) -> io::Result<Option<V>> {
+        match fs::read(self.path(key)?) {
+            Ok(bytes) => Ok(Some(V::from(bytes))),
+            Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn store(&mut self, key: &K, val: &V) -> io::Result<()> {
+        let path = self.path(key)?;
+        let tmp = self.tmp_path(&key.to_string());
+        let res = File::create(&tmp).and_then(|mut file| {
+            file.write_all(val.as_ref())?;
+            file.sync_all()?;
+            fs::rename(&tmp, path)
+        });
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        res
+    }
+
+    fn remove(&mut self, key: &K) -> io::Result<()> {
+        match fs::remove_file(self.path(key)?) {
+            Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+}
+
+/// DirtyStore is a write-back key value store over a Backend. Reads load
+/// values from the backend and cache them clean, writes and removes mark the
+/// entry dirty, and flush() persists only the dirty entries.
+pub struct DirtyStore<K, V, B> where B: Backend<K, V> {
+    // A dirty None is a pending remove, a clean None a known missing key.
+    entries: HashMap<K, Dirty<Option<V>>>,
+    backend: B,
+}
+
+impl<K, V, B> DirtyStore<K, V, B> where K: Eq + Hash + Clone, B: Backend<K, V> {
+    /// Create a new DirtyStore over backend with nothing cached.
+    pub fn new(backend: B) -> DirtyStore<K, V, B> {
+        DirtyStore {
+            entries: HashMap::new(),
+            backend,
+        }
+    }
+
+    /// Read the backend.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Returns true if the entry for key is dirty, false otherwise.
+    pub fn dirty(&self, key: &K) -> bool {
+        self.entries.get(key).map(Dirty::dirty).unwrap_or(false)
+    }
+
+    /// Returns true if any entry is dirty, false otherwise.
+    pub fn any_dirty(&self) -> bool {
+        self.entries.values().any(Dirty::dirty)
+    }
+
+    /// Iterate over the keys of the dirty entries.
+    pub fn dirty_keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter()
+            .filter(|&(_, v)| v.dirty())
+            .map(|(k, _)| k)
+    }
+
+    /// Read the value for key, loading it from the backend if not cached.
+    pub fn get(&mut self, key: &K) -> Result<Option<&V>, B::Error> {
+        self.load(key)?;
+        Ok(self.entries[key].read().as_ref())
+    }
+
+    /// Writable value return, loading it from the backend if not cached and
+    /// marking the entry dirty if there is a value.
+    pub fn get_mut(&mut self, key: &K) -> Result<Option<&mut V>, B::Error> {
+        self.load(key)?;
+        let entry = self.entries.get_mut(key).expect("entry was just loaded");
+        match entry.is_some() {
+            true => Ok(entry.write().as_mut()),
+            false => Ok(None),
+        }
+    }
+
+    /// Sets the value for key without loading it, marking the entry dirty.
+    pub fn insert(&mut self, key: K, val: V) {
+        self.entries.insert(key, Dirty::new(Some(val)));
+    }
+
+    /// Removes the value for key without loading it, marking the entry dirty.
+    pub fn remove(&mut self, key: K) {
+        self.entries.insert(key, Dirty::new(None));
+    }
+
+    /// Persists the dirty entries to the backend, clearing their dirty flags.
+    /// Returns the number of entries persisted. On error the failed entry and
+    /// any not yet persisted stay dirty.
+    pub fn flush(&mut self) -> Result<usize, B::Error> {
+        let backend = &mut self.backend;
+        let mut flushed = 0;
+        for (key, entry) in self.entries.iter_mut() {
+            let persisted = entry.flush(|val| match *val {
+                Some(ref val) => backend.store(key, val),
+                None => backend.remove(key),
+            })?;
+            if persisted {
+                flushed += 1;
+            }
+        }
+        Ok(flushed)
+    }
+
+    /// Drops the cached clean entries, so they are loaded again on the next
+    /// read.
+    pub fn evict_clean(&mut self) {
+        self.entries.retain(|_, v| v.dirty());
+    }
+
+    /// Consumes the DirtyStore, returning the backend. Dirty entries are
+    /// dropped without being persisted.
+    pub fn into_backend(self) -> B {
+        self.backend
+    }
+
+    fn load(&mut self, key: &K) -> Result<(), B::Error> {
+        if !self.entries.contains_key(key) {
+            let val = self.backend.load(key)?;
+            self.entries.insert(key.clone(), Dirty::new_clean(val));
+        }
+        Ok(())
+    }
+}
+
+impl<K, V, B> fmt::Debug for DirtyStore<K, V, B>
+    where K: fmt::Debug, V: fmt::Debug, B: Backend<K, V> + fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DirtyStore")
+            .field("entries", &self.entries)
+            .field("backend", &self.backend)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirtyStore, FsBackend, MemoryBackend};
+    use std::collections::HashMap;
+    use std::env;
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::string::String;
+    use std::vec::Vec;
+
+    #[test]
+    fn reads_populate_clean() {
+        let mut map = HashMap::new();
+        map.insert("a", 1);
+        let mut store = DirtyStore::new(MemoryBackend::from(map));
+        assert!(store.get(&"a") == Ok(Some(&1)));
+        assert!(store.get(&"b") == Ok(None));
+        assert!(!store.any_dirty());
+        assert!(store.get_mut(&"b") == Ok(None));
+        assert!(!store.dirty(&"b"));
+    }
+
+    #[test]
+    fn flush_persists_dirty_only() {
+        let mut map = HashMap::new();
+        map.insert("a", 1);
+        map.insert("b", 2);
+        let mut store = DirtyStore::new(MemoryBackend::from(map));
+        *store.get_mut(&"a").unwrap().unwrap() += 10;
+        store.get(&"b").unwrap();
+        store.insert("c", 3);
+        store.remove("b");
+        assert!(store.dirty_keys().count() == 3);
+        assert!(store.flush() == Ok(3));
+        assert!(!store.any_dirty());
+        assert!(store.flush() == Ok(0));
+        let stored = store.backend().as_map();
+        assert!(stored.len() == 2 && stored["a"] == 11 && stored["c"] == 3);
+    }
+
+    #[test]
+    fn fs_backend() {
+        let dir = env::temp_dir().join(std::format!("dirty-store-{}", std::process::id()));
+        let mut store: DirtyStore<String, Vec<u8>, _> = DirtyStore::new(FsBackend::new(&dir).unwrap());
+        store.insert(String::from("greeting"), b"hello".to_vec());
+        assert!(store.flush().unwrap() == 1);
+        assert!(fs::read(dir.join("greeting")).unwrap() == b"hello");
+        let mut store: DirtyStore<String, Vec<u8>, _> = DirtyStore::new(store.into_backend());
+        assert!(store.get(&String::from("greeting")).unwrap().unwrap() == b"hello");
+        store.remove(String::from("greeting"));
+        store.flush().unwrap();
+        assert!(!dir.join("greeting").exists());
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn fs_backend_rejects_paths() {
+        let dir = env::temp_dir().join(std::format!("dirty-store-keys-{}", std::process::id()));
+        let backend = FsBackend::new(&dir).unwrap();
+        for key in &["../x", "/etc/passwd", "a/b", ".", "..", "", ".a.tmp"] {
+            assert!(backend.path(key).unwrap_err().kind() == ErrorKind::InvalidInput);
+        }
+        assert!(backend.path(&"a.b").unwrap() == dir.join("a.b"));
+        let mut store: DirtyStore<&str, Vec<u8>, _> = DirtyStore::new(backend);
+        store.insert("../x", b"escape".to_vec());
+        assert!(store.flush().unwrap_err().kind() == ErrorKind::InvalidInput);
+        assert!(store.dirty(&"../x"));
+        let mut store: DirtyStore<&str, Vec<u8>, _> = DirtyStore::new(store.into_backend());
+        store.insert("x", b"x".to_vec());
+        store.insert("x.tmp", b"not a temp file".to_vec());
+        assert!(store.flush().unwrap() == 2);
+        assert!(fs::read(dir.join("x")).unwrap() == b"x");
+        assert!(fs::read(dir.join("x.tmp")).unwrap() == b"not a temp file");
+        assert!(fs::read_dir(&dir).unwrap().count() == 2);
+        // Renaming over a directory fails, the temporary file is removed.
+        fs::create_dir(dir.join("d")).unwrap();
+        store.insert("d", b"d".to_vec());
+        assert!(store.flush().is_err());
+        assert!(!dir.join(".d.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
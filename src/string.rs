@@ -0,0 +1,192 @@
+use alloc::string::String;
+use core::ops::{Deref, Range};
+
+use ranges::RangeSet;
+
+/// DirtyString wraps a String with a dirty flag and the byte ranges edited
+/// since the last clear(), in terms of the current contents.
+/// Insertions and replacements shift the ranges after them, a removal records
+/// an empty range where the removed text was.
+/// Use deref (&*dirty_string) to read the text.
+pub struct DirtyString {
+    value: String,
+    dirty: bool,
+    ranges: RangeSet,
+}
+
+impl DirtyString {
+    /// Create a new DirtyString with all of its text edited.
+    pub fn new(val: String) -> DirtyString {
+        let mut ranges = RangeSet::new();
+        ranges.insert(0..val.len());
+        DirtyString {
+            value: val,
+            dirty: true,
+            ranges,
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the sorted, non overlapping byte ranges edited since the last
+    /// clear().
+    pub fn dirty_ranges(&self) -> &[Range<usize>] {
+        self.ranges.as_slice()
+    }
+
+    /// Clears the dirty flag and edited ranges.
+    pub fn clear(&mut self) {
+        self.dirty = false;
+        self.ranges.clear();
+    }
+
+    /// Read the text.
+    pub fn read(&self) -> &str {
+        &self.value
+    }
+
+    /// Appends a char.
+    pub fn push(&mut self, ch: char) {
+        let at = self.value.len();
+        self.value.push(ch);
+        self.edited(at..at, ch.len_utf8());
+    }
+
+    /// Appends a string slice.
+    pub fn push_str(&mut self, s: &str) {
+        let at = self.value.len();
+        self.value.push_str(s);
+        self.edited(at..at, s.len());
+    }
+
+    /// Inserts a char at byte index idx.
+    pub fn insert(&mut self, idx: usize, ch: char) {
+        self.value.insert(idx, ch);
+        self.edited(idx..idx, ch.len_utf8());
+    }
+
+    /// Inserts a string slice at byte index idx.
+    pub fn insert_str(&mut self, idx: usize, s: &str) {
+        self.value.insert_str(idx, s);
+        self.edited(idx..idx, s.len());
+    }
+
+    /// Replaces the bytes in range with a string slice.
+    pub fn replace_range(&mut self, range: Range<usize>, s: &str) {
+        self.value.replace_range(range.clone(), s);
+        self.edited(range, s.len());
+    }
+
+    /// Removes the char at byte index idx and returns it.
+    pub fn remove(&mut self, idx: usize) -> char {
+        let ch = self.value.remove(idx);
+        self.edited(idx..idx + ch.len_utf8(), 0);
+        ch
+    }
+
+    /// Removes the last char and returns it.
+    pub fn pop(&mut self) -> Option<char> {
+        let ch = self.value.pop()?;
+        let at = self.value.len();
+        self.edited(at..at + ch.len_utf8(), 0);
+        Some(ch)
+    }
+
+    /// Shortens the text to new_len bytes.
+    pub fn truncate(&mut self, new_len: usize) {
+        let len = self.value.len();
+        if new_len < len {
+            self.value.truncate(new_len);
+            self.edited(new_len..len, 0);
+        }
+    }
+
+    /// Consumes the DirtyString, returning the inner String.
+    pub fn into_inner(self) -> String {
+        self.value
+    }
+
+    fn edited(&mut self, range: Range<usize>, new_len: usize) {
+        self.dirty = true;
+        self.ranges.splice(range, new_len);
+    }
+}
+
+impl From<String> for DirtyString {
+    fn from(val: String) -> DirtyString {
+        DirtyString::new(val)
+    }
+}
+
+impl<'a> From<&'a str> for DirtyString {
+    fn from(val: &'a str) -> DirtyString {
+        DirtyString::new(String::from(val))
+    }
+}
+
+impl Deref for DirtyString {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl Default for DirtyString {
+    fn default() -> Self {
+        DirtyString::new(String::new())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::DirtyString;
+
+    fn clean(s: &str) -> DirtyString {
+        let mut string = DirtyString::from(s);
+        string.clear();
+        string
+    }
+
+    #[test]
+    fn new_all_edited() {
+        let string = DirtyString::from("abc");
+        assert!(string.dirty());
+        assert!(string.dirty_ranges() == [0..3]);
+    }
+
+    #[test]
+    fn push_records_range() {
+        let mut string = clean("abc");
+        assert!(!string.dirty());
+        string.push_str("de");
+        string.push('f');
+        assert!(string.dirty());
+        assert!(string.dirty_ranges() == [3..6]);
+        assert!(&*string == "abcdef");
+    }
+
+    #[test]
+    fn insert_shifts_ranges() {
+        let mut string = clean("hello world");
+        string.replace_range(6..11, "there");
+        string.insert_str(0, ">> ");
+        assert!(string.dirty_ranges() == [0..3, 9..14]);
+        assert!(string.read() == ">> hello there");
+    }
+
+    #[test]
+    fn removal_records_empty_range() {
+        let mut string = clean("abcdef");
+        string.remove(1);
+        assert!(string.dirty_ranges() == [1..1]);
+        string.truncate(3);
+        assert!(string.dirty_ranges() == [1..1, 3..3]);
+        assert!(string.pop() == Some('d'));
+        assert!(string.dirty_ranges() == [1..1, 2..2]);
+        assert!(&*string == "ac");
+    }
+}
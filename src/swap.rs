@@ -0,0 +1,123 @@
+use core::fmt;
+use core::mem;
+use core::ops::Deref;
+
+/// DirtySwap double buffers a value of type T, writes go to the back buffer
+/// and set the dirty flag while reads see the stable front buffer until
+/// publish() swaps them.
+/// Use front() or deref (*dirty_swap) to read the published value.
+pub struct DirtySwap<T> where T: Clone {
+    front: T,
+    back: T,
+    dirty: bool,
+}
+
+impl<T> DirtySwap<T> where T: Clone {
+    /// Create a new DirtySwap with both buffers holding val, it starts clean
+    /// as there is nothing to publish.
+    pub fn new(val: T) -> DirtySwap<T> {
+        DirtySwap {
+            back: val.clone(),
+            front: val,
+            dirty: false,
+        }
+    }
+
+    /// Returns true if the back buffer was written since the last publish(),
+    /// false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Read the published front buffer.
+    pub fn front(&self) -> &T {
+        &self.front
+    }
+
+    /// Read the back buffer.
+    pub fn back(&self) -> &T {
+        &self.back
+    }
+
+    /// Writable back buffer return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.back
+    }
+
+    /// Publishes the back buffer if dirty by swapping it to the front, then
+    /// copies it to the new back buffer so writes continue from it and clears
+    /// the dirty flag. Returns true if published.
+    pub fn publish(&mut self) -> bool {
+        if !self.dirty {
+            return false;
+        }
+        mem::swap(&mut self.front, &mut self.back);
+        self.back.clone_from(&self.front);
+        self.dirty = false;
+        true
+    }
+
+    /// Consumes the DirtySwap, returning the front buffer.
+    pub fn into_inner(self) -> T {
+        self.front
+    }
+}
+
+impl<T> Deref for DirtySwap<T> where T: Clone {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.front
+    }
+}
+
+impl<T> Default for DirtySwap<T> where T: Clone + Default {
+    fn default() -> Self {
+        DirtySwap::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for DirtySwap<T> where T: Clone + fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DirtySwap")
+            .field("front", &self.front)
+            .field("back", &self.back)
+            .field("dirty", &self.dirty)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtySwap;
+
+    #[test]
+    fn writes_hidden_until_publish() {
+        let mut swap = DirtySwap::new(1);
+        assert!(!swap.dirty());
+        *swap.write() += 1;
+        assert!(swap.dirty());
+        assert!(*swap == 1);
+        assert!(*swap.back() == 2);
+        assert!(swap.publish());
+        assert!(!swap.dirty());
+        assert!(*swap.front() == 2);
+    }
+
+    #[test]
+    fn publish_clean_is_noop() {
+        let mut swap = DirtySwap::new(1);
+        assert!(!swap.publish());
+        assert!(*swap == 1);
+    }
+
+    #[test]
+    fn writes_continue_from_published() {
+        let mut swap = DirtySwap::new(1);
+        *swap.write() += 1;
+        swap.publish();
+        *swap.write() += 1;
+        swap.publish();
+        assert!(*swap == 3);
+    }
+}
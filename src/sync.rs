@@ -0,0 +1,347 @@
+//! Locks which carry a dirty flag alongside the value they guard. The flag is
+//! set by a guard before it releases the lock and only cleared while the lock
+//! is held, so a write is never lost between reading the value and clearing
+//! the flag.
+
+use core::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LockResult, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard,
+                RwLockWriteGuard};
+
+fn map_result<G, U, F>(result: LockResult<G>, f: F) -> LockResult<U> where F: FnOnce(G) -> U {
+    match result {
+        Ok(guard) => Ok(f(guard)),
+        Err(err) => Err(PoisonError::new(f(err.into_inner()))),
+    }
+}
+
+/// DirtyRwLock is a reader-writer lock around a value of type T with a dirty
+/// flag set whenever a write guard is mutably dereferenced.
+/// Poisoning follows std::sync::RwLock.
+pub struct DirtyRwLock<T> {
+    lock: RwLock<T>,
+    dirty: AtomicBool,
+}
+
+impl<T> DirtyRwLock<T> {
+    /// Create a new DirtyRwLock.
+    pub fn new(val: T) -> DirtyRwLock<T> {
+        DirtyRwLock {
+            lock: RwLock::new(val),
+            dirty: AtomicBool::new(true),
+        }
+    }
+
+    /// Create a new clean DirtyRwLock.
+    pub fn new_clean(val: T) -> DirtyRwLock<T> {
+        DirtyRwLock {
+            lock: RwLock::new(val),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty.load(Ordering::Acquire)
+    }
+
+    /// Clears the dirty flag, taking the write lock so a write in progress
+    /// finishes first. The flag is cleared even if the lock is poisoned.
+    /// Deadlocks if the calling thread holds a guard of this lock.
+    pub fn clear(&self) {
+        let _guard = self.lock.write().unwrap_or_else(PoisonError::into_inner);
+        self.dirty.store(false, Ordering::Release);
+    }
+
+    /// Locks for shared read access.
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
+        self.lock.read()
+    }
+
+    /// Locks for exclusive write access, the dirty flag is set on drop if the
+    /// guard was mutably dereferenced.
+    pub fn write(&self) -> LockResult<DirtyRwLockWriteGuard<'_, T>> {
+        map_result(self.lock.write(), |guard| DirtyRwLockWriteGuard {
+            guard,
+            dirty: &self.dirty,
+            modified: false,
+        })
+    }
+
+    /// Locks for shared read access only if dirty, clearing the dirty flag.
+    /// The flag is cleared while the read lock is held so no write can slip
+    /// in between.
+    pub fn read_dirty(&self) -> LockResult<Option<RwLockReadGuard<'_, T>>> {
+        map_result(self.lock.read(), |guard| {
+            match self.dirty.swap(false, Ordering::AcqRel) {
+                true => Some(guard),
+                false => None,
+            }
+        })
+    }
+
+    /// Consumes the DirtyRwLock, returning the inner value.
+    pub fn into_inner(self) -> LockResult<T> {
+        self.lock.into_inner()
+    }
+}
+
+impl<T> Default for DirtyRwLock<T> where T: Default {
+    fn default() -> Self {
+        DirtyRwLock::new(T::default())
+    }
+}
+
+/// DirtyRwLockWriteGuard gives exclusive write access to the value of a
+/// DirtyRwLock.
+/// The dirty flag is set on drop, before the lock is released, if the guard
+/// was mutably dereferenced.
+pub struct DirtyRwLockWriteGuard<'a, T: 'a> {
+    guard: RwLockWriteGuard<'a, T>,
+    dirty: &'a AtomicBool,
+    modified: bool,
+}
+
+impl<'a, T> DirtyRwLockWriteGuard<'a, T> {
+    /// Returns true if the guard has been mutably dereferenced.
+    pub fn modified(&self) -> bool {
+        self.modified
+    }
+}
+
+impl<'a, T> Deref for DirtyRwLockWriteGuard<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<'a, T> DerefMut for DirtyRwLockWriteGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.modified = true;
+        &mut self.guard
+    }
+}
+
+impl<'a, T> Drop for DirtyRwLockWriteGuard<'a, T> {
+    fn drop(&mut self) {
+        if self.modified {
+            self.dirty.store(true, Ordering::Release);
+        }
+    }
+}
+
+/// DirtyMutex is a mutual exclusion lock around a value of type T with a
+/// dirty flag set whenever a guard is mutably dereferenced.
+/// Poisoning follows std::sync::Mutex.
+pub struct DirtyMutex<T> {
+    lock: Mutex<T>,
+    dirty: AtomicBool,
+}
+
+impl<T> DirtyMutex<T> {
+    /// Create a new DirtyMutex.
+    pub fn new(val: T) -> DirtyMutex<T> {
+        DirtyMutex {
+            lock: Mutex::new(val),
+            dirty: AtomicBool::new(true),
+        }
+    }
+
+    /// Create a new clean DirtyMutex.
+    pub fn new_clean(val: T) -> DirtyMutex<T> {
+        DirtyMutex {
+            lock: Mutex::new(val),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty.load(Ordering::Acquire)
+    }
+
+    /// Clears the dirty flag, taking the lock so a write in progress finishes
+    /// first. The flag is cleared even if the lock is poisoned.
+    /// Deadlocks if the calling thread holds a guard of this lock.
+    pub fn clear(&self) {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        self.dirty.store(false, Ordering::Release);
+    }
+
+    /// Locks the value, the dirty flag is set on drop if the guard was
+    /// mutably dereferenced.
+    pub fn lock(&self) -> LockResult<DirtyMutexGuard<'_, T>> {
+        map_result(self.lock.lock(), |guard| DirtyMutexGuard {
+            guard,
+            dirty: &self.dirty,
+            modified: false,
+        })
+    }
+
+    /// Locks the value only if dirty, clearing the dirty flag.
+    /// Returns None without taking the lock when clean, so a periodic flush
+    /// doesn't contend with writers needlessly.
+    pub fn lock_if_dirty(&self) -> LockResult<Option<DirtyMutexGuard<'_, T>>> {
+        if !self.dirty() {
+            return Ok(None);
+        }
+        map_result(self.lock(), |guard| {
+            // Check again under the lock as another caller may have cleared it.
+            match self.dirty.swap(false, Ordering::AcqRel) {
+                true => Some(guard),
+                false => None,
+            }
+        })
+    }
+
+    /// Consumes the DirtyMutex, returning the inner value.
+    pub fn into_inner(self) -> LockResult<T> {
+        self.lock.into_inner()
+    }
+}
+
+impl<T> Default for DirtyMutex<T> where T: Default {
+    fn default() -> Self {
+        DirtyMutex::new(T::default())
+    }
+}
+
+/// DirtyMutexGuard gives exclusive access to the value of a DirtyMutex.
+/// The dirty flag is set on drop, before the lock is released, if the guard
+/// was mutably dereferenced.
+pub struct DirtyMutexGuard<'a, T: 'a> {
+    guard: MutexGuard<'a, T>,
+    dirty: &'a AtomicBool,
+    modified: bool,
+}
+
+impl<'a, T> DirtyMutexGuard<'a, T> {
+    /// Returns true if the guard has been mutably dereferenced.
+    pub fn modified(&self) -> bool {
+        self.modified
+    }
+}
+
+impl<'a, T> Deref for DirtyMutexGuard<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<'a, T> DerefMut for DirtyMutexGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.modified = true;
+        &mut self.guard
+    }
+}
+
+impl<'a, T> Drop for DirtyMutexGuard<'a, T> {
+    fn drop(&mut self) {
+        if self.modified {
+            self.dirty.store(true, Ordering::Release);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirtyMutex, DirtyRwLock};
+    use std::sync::PoisonError;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn write_guard_sets_flag_on_drop() {
+        let lock = DirtyRwLock::new_clean(0);
+        {
+            let guard = lock.write().unwrap();
+            assert!(*guard == 0);
+            assert!(!guard.modified());
+        }
+        assert!(!lock.dirty());
+        {
+            let mut guard = lock.write().unwrap();
+            *guard += 1;
+            assert!(!lock.dirty());
+        }
+        assert!(lock.dirty());
+    }
+
+    #[test]
+    fn read_dirty_clears_flag() {
+        let lock = DirtyRwLock::new(1);
+        assert!(lock.read_dirty().unwrap().map(|v| *v) == Some(1));
+        assert!(lock.read_dirty().unwrap().is_none());
+        *lock.write().unwrap() = 2;
+        assert!(*lock.read().unwrap() == 2);
+        assert!(lock.dirty());
+        lock.clear();
+        assert!(lock.into_inner().unwrap() == 2);
+    }
+
+    #[test]
+    fn writes_across_threads() {
+        let lock = Arc::new(DirtyRwLock::new_clean(0));
+        let handles: std::vec::Vec<_> = (0..4).map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || *lock.write().unwrap() += 1)
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert!(lock.read_dirty().unwrap().map(|v| *v) == Some(4));
+    }
+
+    #[test]
+    fn poisoned_write_still_marks() {
+        let lock = Arc::new(DirtyRwLock::new_clean(0));
+        let other = lock.clone();
+        let _ = thread::spawn(move || {
+            let mut guard = other.write().unwrap();
+            *guard += 1;
+            panic!("poison");
+        }).join();
+        assert!(lock.read().is_err());
+        assert!(lock.dirty());
+        let guard = lock.read_dirty().unwrap_err().into_inner();
+        assert!(guard.map(|v| *v) == Some(1));
+        *lock.write().unwrap_or_else(PoisonError::into_inner) += 1;
+        assert!(lock.dirty());
+        lock.clear();
+        assert!(!lock.dirty());
+    }
+
+    #[test]
+    fn mutex_guard_sets_flag_on_drop() {
+        let mutex = DirtyMutex::new_clean(0);
+        assert!(mutex.lock_if_dirty().unwrap().is_none());
+        *mutex.lock().unwrap() += 1;
+        assert!(mutex.dirty());
+        {
+            let guard = mutex.lock_if_dirty().unwrap().unwrap();
+            assert!(*guard == 1);
+            assert!(!mutex.dirty());
+        }
+        assert!(!mutex.dirty());
+        assert!(mutex.lock_if_dirty().unwrap().is_none());
+        *mutex.lock().unwrap() += 1;
+        mutex.clear();
+        assert!(!mutex.dirty());
+    }
+
+    #[test]
+    fn mutex_periodic_flush() {
+        let mutex = Arc::new(DirtyMutex::new_clean(0));
+        let writer = mutex.clone();
+        thread::spawn(move || {
+            for _ in 0..100 {
+                *writer.lock().unwrap() += 1;
+            }
+        }).join().unwrap();
+        let flushed = mutex.lock_if_dirty().unwrap().map(|v| *v);
+        assert!(flushed == Some(100));
+        assert!(*mutex.lock().unwrap() == 100);
+    }
+}
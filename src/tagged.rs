@@ -0,0 +1,171 @@
+use core::fmt;
+use core::ops::{BitAnd, BitOr, Deref, Not};
+
+/// Aspects is implemented for bitflags style sets usable with TaggedDirty,
+/// any Copy type with the bit operators where Default is the empty set such as
+/// the integers or a bitflags type deriving Default.
+pub trait Aspects: Copy + Default + PartialEq + BitOr<Output = Self> + BitAnd<Output = Self> +
+                   Not<Output = Self> {}
+
+impl<F> Aspects for F
+    where F: Copy + Default + PartialEq + BitOr<Output = F> + BitAnd<Output = F> + Not<Output = F> {}
+
+/// TaggedDirty wraps a value of type T with a set of dirty aspects F, so
+/// consumers may react to only the aspects they care about.
+/// A new TaggedDirty starts with every aspect dirty.
+pub struct TaggedDirty<T, F> {
+    value: T,
+    aspects: F,
+}
+
+impl<T, F> TaggedDirty<T, F> where F: Aspects {
+    /// Create a new TaggedDirty.
+    pub fn new(val: T) -> TaggedDirty<T, F> {
+        TaggedDirty {
+            value: val,
+            aspects: !F::default(),
+        }
+    }
+
+    /// Create a new clean TaggedDirty.
+    pub fn new_clean(val: T) -> TaggedDirty<T, F> {
+        TaggedDirty {
+            value: val,
+            aspects: F::default(),
+        }
+    }
+
+    /// Returns true if any aspect is dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.aspects != F::default()
+    }
+
+    /// Returns the dirty aspects.
+    pub fn aspects(&self) -> F {
+        self.aspects
+    }
+
+    /// Returns true if any of the given aspects are dirty, false otherwise.
+    pub fn dirty_any(&self, aspects: F) -> bool {
+        self.aspects & aspects != F::default()
+    }
+
+    /// Returns true if all of the given aspects are dirty, false otherwise.
+    pub fn dirty_all(&self, aspects: F) -> bool {
+        self.aspects & aspects == aspects
+    }
+
+    /// Marks the given aspects dirty.
+    pub fn mark(&mut self, aspects: F) {
+        self.aspects = self.aspects | aspects;
+    }
+
+    /// Writable value return, marks the given aspects dirty.
+    pub fn write_tagged(&mut self, aspects: F) -> &mut T {
+        self.mark(aspects);
+        &mut self.value
+    }
+
+    /// Set the value, marks the given aspects dirty.
+    pub fn set_tagged(&mut self, val: T, aspects: F) {
+        self.value = val;
+        self.mark(aspects);
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Clears the given aspects.
+    pub fn clear(&mut self, aspects: F) {
+        self.aspects = self.aspects & !aspects;
+    }
+
+    /// Clears every aspect.
+    pub fn clear_all(&mut self) {
+        self.aspects = F::default();
+    }
+
+    /// Read the value only if any of the given aspects are dirty.
+    pub fn read_dirty(&self, aspects: F) -> Option<&T> {
+        match self.dirty_any(aspects) {
+            true => Some(&self.value),
+            false => None,
+        }
+    }
+
+    /// Read the value only if any of the given aspects are dirty, clearing
+    /// them.
+    pub fn take_dirty(&mut self, aspects: F) -> Option<&T> {
+        match self.dirty_any(aspects) {
+            true => {
+                self.clear(aspects);
+                Some(&self.value)
+            }
+            false => None,
+        }
+    }
+
+    /// Consumes the TaggedDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, F> Deref for TaggedDirty<T, F> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, F> Default for TaggedDirty<T, F> where T: Default, F: Aspects {
+    fn default() -> Self {
+        TaggedDirty::new(T::default())
+    }
+}
+
+impl<T, F> fmt::Debug for TaggedDirty<T, F> where T: fmt::Debug, F: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TaggedDirty")
+            .field("value", &self.value)
+            .field("aspects", &self.aspects)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaggedDirty;
+
+    const POSITION: u8 = 1;
+    const ROTATION: u8 = 1 << 1;
+    const SCALE: u8 = 1 << 2;
+
+    #[test]
+    fn new_all_dirty() {
+        let dirty: TaggedDirty<i32, u8> = TaggedDirty::new(0);
+        assert!(dirty.dirty_all(POSITION | ROTATION | SCALE));
+        let clean: TaggedDirty<i32, u8> = TaggedDirty::new_clean(0);
+        assert!(!clean.dirty());
+    }
+
+    #[test]
+    fn aspects_tracked_separately() {
+        let mut dirty = TaggedDirty::new_clean((0, 0, 1));
+        dirty.write_tagged(POSITION).0 = 5;
+        dirty.write_tagged(SCALE).2 = 2;
+        assert!(dirty.aspects() == POSITION | SCALE);
+        assert!(dirty.dirty_any(ROTATION | SCALE));
+        assert!(!dirty.dirty_all(ROTATION | SCALE));
+        assert!(dirty.read_dirty(ROTATION).is_none());
+        assert!(dirty.read_dirty(POSITION) == Some(&(5, 0, 2)));
+        assert!(dirty.aspects() == POSITION | SCALE);
+        assert!(dirty.take_dirty(ROTATION).is_none());
+        assert!(dirty.take_dirty(POSITION) == Some(&(5, 0, 2)));
+        assert!(dirty.aspects() == SCALE);
+        dirty.clear_all();
+        assert!(!dirty.dirty());
+    }
+}
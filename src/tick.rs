@@ -0,0 +1,105 @@
+use core::fmt;
+use core::ops::Deref;
+
+/// TickDirty wraps a value of type T with the tick of its last write, where
+/// ticks are supplied by the caller, e.g. a frame or schedule counter.
+/// Consumers compare against the tick they last ran at instead of clearing a
+/// shared flag.
+/// Ticks are compared with wraparound, a tick is newer than another if it is
+/// less than 2^31 ticks ahead of it.
+pub struct TickDirty<T> {
+    value: T,
+    tick: u32,
+}
+
+impl<T> TickDirty<T> {
+    /// Create a new TickDirty written at tick.
+    pub const fn new(val: T, tick: u32) -> TickDirty<T> {
+        TickDirty {
+            value: val,
+            tick,
+        }
+    }
+
+    /// Returns the tick of the last write.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// Returns true if the value was written after tick, false otherwise.
+    pub fn changed_since(&self, tick: u32) -> bool {
+        self.tick.wrapping_sub(tick) as i32 > 0
+    }
+
+    /// Writable value return, records tick as the last write.
+    pub fn write(&mut self, tick: u32) -> &mut T {
+        self.tick = tick;
+        &mut self.value
+    }
+
+    /// Set the value, records tick as the last write.
+    pub fn set(&mut self, val: T, tick: u32) {
+        self.value = val;
+        self.tick = tick;
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Read the value only if written after tick.
+    pub fn read_if_changed(&self, tick: u32) -> Option<&T> {
+        match self.changed_since(tick) {
+            true => Some(&self.value),
+            false => None,
+        }
+    }
+
+    /// Consumes the TickDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for TickDirty<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> fmt::Debug for TickDirty<T> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TickDirty")
+            .field("value", &self.value)
+            .field("tick", &self.tick)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TickDirty;
+
+    #[test]
+    fn changed_since_last_run() {
+        let mut dirty = TickDirty::new(0, 1);
+        assert!(dirty.changed_since(0));
+        assert!(!dirty.changed_since(1));
+        *dirty.write(5) += 1;
+        assert!(dirty.tick() == 5);
+        assert!(dirty.changed_since(3));
+        assert!(dirty.read_if_changed(5).is_none());
+        assert!(dirty.read_if_changed(4) == Some(&1));
+    }
+
+    #[test]
+    fn ticks_wrap_around() {
+        let mut dirty = TickDirty::new(0, u32::MAX - 1);
+        dirty.set(1, 2);
+        assert!(dirty.changed_since(u32::MAX));
+        assert!(!dirty.changed_since(3));
+        assert!(!TickDirty::new(0, u32::MAX).changed_since(2));
+    }
+}
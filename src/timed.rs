@@ -0,0 +1,149 @@
+use std::fmt;
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+
+/// TimedDirty wraps a value of type T like Dirty and records when it last
+/// transitioned from clean to dirty, so callers may tell how long changes
+/// have been pending, e.g. to debounce an autosave.
+pub struct TimedDirty<T> {
+    value: T,
+    dirty_since: Option<Instant>,
+}
+
+impl<T> TimedDirty<T> {
+    /// Create a new TimedDirty, dirty since now.
+    pub fn new(val: T) -> TimedDirty<T> {
+        TimedDirty {
+            value: val,
+            dirty_since: Some(Instant::now()),
+        }
+    }
+
+    /// Create a new clean TimedDirty.
+    pub fn new_clean(val: T) -> TimedDirty<T> {
+        TimedDirty {
+            value: val,
+            dirty_since: None,
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty_since.is_some()
+    }
+
+    /// Returns when the value became dirty, or None if clean.
+    pub fn dirty_since(&self) -> Option<Instant> {
+        self.dirty_since
+    }
+
+    /// Returns how long the value has been dirty, or None if clean.
+    pub fn dirty_for(&self) -> Option<Duration> {
+        self.dirty_since.map(|since| since.elapsed())
+    }
+
+    /// Writable value return, sets the dirty flag recording the time if clean.
+    pub fn write(&mut self) -> &mut T {
+        self.mark();
+        &mut self.value
+    }
+
+    /// Set the value, sets the dirty flag recording the time if clean.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.mark();
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Clears the dirty flag.
+    pub fn clear(&mut self) {
+        self.dirty_since = None;
+    }
+
+    /// Read the value only if modified since last read.
+    pub fn read_dirty(&self) -> Option<&T> {
+        match self.dirty() {
+            true => Some(&self.value),
+            false => None,
+        }
+    }
+
+    /// Consumes the TimedDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    fn mark(&mut self) {
+        if self.dirty_since.is_none() {
+            self.dirty_since = Some(Instant::now());
+        }
+    }
+}
+
+impl<T> Deref for TimedDirty<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Default for TimedDirty<T> where T: Default {
+    fn default() -> Self {
+        TimedDirty::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for TimedDirty<T> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TimedDirty")
+            .field("value", &self.value)
+            .field("dirty_since", &self.dirty_since)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimedDirty;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn new_dirty_since_now() {
+        let dirty = TimedDirty::new(0);
+        assert!(dirty.dirty());
+        assert!(dirty.dirty_for().unwrap() < Duration::from_secs(60));
+    }
+
+    #[test]
+    fn new_clean() {
+        let dirty = TimedDirty::new_clean(0);
+        assert!(!dirty.dirty());
+        assert!(dirty.dirty_since().is_none());
+    }
+
+    #[test]
+    fn clear_resets_time() {
+        let mut dirty = TimedDirty::new(0);
+        dirty.clear();
+        assert!(!dirty.dirty());
+        assert!(dirty.dirty_since().is_none());
+        assert!(dirty.dirty_for().is_none());
+    }
+
+    #[test]
+    fn time_kept_across_writes() {
+        let mut dirty = TimedDirty::new(0);
+        dirty.clear();
+        *dirty.write() += 1;
+        let since = dirty.dirty_since().unwrap();
+        thread::sleep(Duration::from_millis(2));
+        dirty.set(2);
+        assert!(dirty.dirty_since() == Some(since));
+        assert!(dirty.dirty_for().unwrap() >= Duration::from_millis(2));
+    }
+}
@@ -0,0 +1,504 @@
+use core::ops::{Add, Sub};
+use core::sync::atomic::Ordering;
+
+use all::{ClearAll, DirtyAll};
+use array::DirtyArray;
+use atomic::AtomicDirty;
+use baseline::BaselineDirty;
+use bits::DirtyBits;
+use cell::{DirtyCell, DirtyRefCell};
+use checksum::{Checksum, ChecksumDirty};
+use counted::CountedDirty;
+use delta::DeltaDirty;
+use flag::DirtyFlag;
+use must::MustFlush;
+use observe::ObservedDirty;
+use snapshot::SnapshotDirty;
+use stat::StatDirty;
+use tagged::{Aspects, TaggedDirty};
+use Dirty;
+#[cfg(feature = "alloc")]
+use boxed::DirtyBox;
+#[cfg(feature = "alloc")]
+use btree_map::DirtyBTreeMap;
+#[cfg(feature = "alloc")]
+use buffer::DirtyBuffer;
+#[cfg(feature = "std")]
+use async_dirty::AsyncDirty;
+#[cfg(feature = "std")]
+use cache::DirtyCache;
+#[cfg(feature = "std")]
+use hashed::HashDirty;
+#[cfg(feature = "std")]
+use map::DirtyHashMap;
+#[cfg(feature = "std")]
+use set::DirtySet;
+#[cfg(feature = "std")]
+use sync::{DirtyMutex, DirtyRwLock};
+#[cfg(feature = "std")]
+use timed::TimedDirty;
+#[cfg(feature = "alloc")]
+use grid::DirtyGrid;
+#[cfg(feature = "alloc")]
+use history::HistoryDirty;
+#[cfg(feature = "alloc")]
+use journal::{Apply, JournaledDirty};
+#[cfg(feature = "alloc")]
+use paged::PagedDirty;
+#[cfg(feature = "alloc")]
+use slab::DirtySlab;
+#[cfg(feature = "alloc")]
+use slice::DirtySlice;
+#[cfg(feature = "alloc")]
+use string::DirtyString;
+#[cfg(feature = "alloc")]
+use vec::DirtyVec;
+#[cfg(feature = "tokio")]
+use watch::WatchDirty;
+#[cfg(feature = "notify")]
+use watcher::WatchedDirty;
+#[cfg(feature = "std")]
+use std::hash::{BuildHasher, Hash};
+
+/// Tracked is implemented by the dirty tracking types of this crate so they
+/// may be checked and cleared together, see DirtyGroup.
+pub trait Tracked {
+    /// Returns true if dirty, false otherwise.
+    fn dirty(&self) -> bool;
+
+    /// Clears the dirty flag.
+    fn clear(&mut self);
+}
+
+macro_rules! tuple_tracked {
+    ($($name:ident $index:tt),+) => {
+        /// A tuple is dirty if any member is, clear() clears every member.
+        impl<$($name),+> Tracked for ($($name,)+) where $($name: Tracked),+ {
+            fn dirty(&self) -> bool {
+                $(self.$index.dirty())||+
+            }
+
+            fn clear(&mut self) {
+                $(self.$index.clear();)+
+            }
+        }
+
+        impl<'a, $($name),+> DirtyAll for ($(&'a $name,)+) where $($name: Tracked + ?Sized),+ {
+            fn any_dirty(&self) -> bool {
+                $(self.$index.dirty())||+
+            }
+
+            fn all_dirty(&self) -> bool {
+                $(self.$index.dirty())&&+
+            }
+        }
+
+        impl<'a, $($name),+> DirtyAll for ($(&'a mut $name,)+) where $($name: Tracked + ?Sized),+ {
+            fn any_dirty(&self) -> bool {
+                $(self.$index.dirty())||+
+            }
+
+            fn all_dirty(&self) -> bool {
+                $(self.$index.dirty())&&+
+            }
+        }
+
+        impl<'a, $($name),+> ClearAll for ($(&'a mut $name,)+) where $($name: Tracked + ?Sized),+ {
+            fn clear_all(&mut self) {
+                $(self.$index.clear();)+
+            }
+        }
+    };
+}
+
+tuple_tracked!(A 0);
+tuple_tracked!(A 0, B 1);
+tuple_tracked!(A 0, B 1, C 2);
+tuple_tracked!(A 0, B 1, C 2, D 3);
+tuple_tracked!(A 0, B 1, C 2, D 3, E 4);
+tuple_tracked!(A 0, B 1, C 2, D 3, E 4, F 5);
+tuple_tracked!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+tuple_tracked!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+impl<T, D> Tracked for Dirty<T, D> where T: ?Sized, D: DirtyFlag {
+    fn dirty(&self) -> bool {
+        Dirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        Dirty::clear(self)
+    }
+}
+
+impl<T> Tracked for AtomicDirty<T> {
+    fn dirty(&self) -> bool {
+        AtomicDirty::dirty(self, Ordering::Acquire)
+    }
+
+    fn clear(&mut self) {
+        AtomicDirty::clear(self)
+    }
+}
+
+impl<const N: usize, const WORDS: usize> Tracked for DirtyBits<N, WORDS> {
+    fn dirty(&self) -> bool {
+        DirtyBits::any(self)
+    }
+
+    fn clear(&mut self) {
+        DirtyBits::clear_all(self)
+    }
+}
+
+impl<T> Tracked for DirtyCell<T> where T: Copy {
+    fn dirty(&self) -> bool {
+        DirtyCell::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DirtyCell::clear(self)
+    }
+}
+
+impl<T> Tracked for DirtyRefCell<T> {
+    fn dirty(&self) -> bool {
+        DirtyRefCell::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DirtyRefCell::clear(self)
+    }
+}
+
+impl<T> Tracked for CountedDirty<T> {
+    fn dirty(&self) -> bool {
+        CountedDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        CountedDirty::clear(self)
+    }
+}
+
+impl<T, F> Tracked for ObservedDirty<T, F> where F: FnMut(&T) {
+    fn dirty(&self) -> bool {
+        ObservedDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        ObservedDirty::clear(self)
+    }
+}
+
+impl<T> Tracked for BaselineDirty<T> where T: Clone + PartialEq {
+    fn dirty(&self) -> bool {
+        BaselineDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        BaselineDirty::clear(self)
+    }
+}
+
+impl<T> Tracked for MustFlush<T> {
+    fn dirty(&self) -> bool {
+        MustFlush::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        MustFlush::clear(self)
+    }
+}
+
+impl<T> Tracked for SnapshotDirty<T> where T: Clone {
+    fn dirty(&self) -> bool {
+        SnapshotDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        SnapshotDirty::clear(self)
+    }
+}
+
+impl<T, F> Tracked for TaggedDirty<T, F> where F: Aspects {
+    fn dirty(&self) -> bool {
+        TaggedDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        TaggedDirty::clear_all(self)
+    }
+}
+
+impl<T> Tracked for DeltaDirty<T> where T: Sub<Output = T> + Clone {
+    fn dirty(&self) -> bool {
+        DeltaDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DeltaDirty::clear(self)
+    }
+}
+
+impl<T, const N: usize, const WORDS: usize> Tracked for DirtyArray<T, N, WORDS> {
+    fn dirty(&self) -> bool {
+        self.any_dirty()
+    }
+
+    fn clear(&mut self) {
+        self.clear_all()
+    }
+}
+
+impl<T> Tracked for StatDirty<T> where T: Copy + PartialOrd + Add<Output = T> {
+    fn dirty(&self) -> bool {
+        StatDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        StatDirty::clear(self)
+    }
+}
+
+impl<B, C> Tracked for ChecksumDirty<B, C> where B: AsRef<[u8]>, C: Checksum {
+    fn dirty(&self) -> bool {
+        ChecksumDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        ChecksumDirty::clear(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Tracked for AsyncDirty<T> {
+    fn dirty(&self) -> bool {
+        AsyncDirty::dirty(self, Ordering::Acquire)
+    }
+
+    fn clear(&mut self) {
+        AsyncDirty::clear(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Tracked for DirtySet<T> where T: Eq + Hash + Clone {
+    fn dirty(&self) -> bool {
+        DirtySet::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DirtySet::clear(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Tracked for DirtyMutex<T> {
+    fn dirty(&self) -> bool {
+        DirtyMutex::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DirtyMutex::clear(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Tracked for DirtyRwLock<T> {
+    fn dirty(&self) -> bool {
+        DirtyRwLock::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DirtyRwLock::clear(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Tracked for TimedDirty<T> {
+    fn dirty(&self) -> bool {
+        TimedDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        TimedDirty::clear(self)
+    }
+}
+
+/// dirty() rehashes the value, see HashDirty::check().
+#[cfg(feature = "std")]
+impl<T, S> Tracked for HashDirty<T, S> where T: Hash, S: BuildHasher {
+    fn dirty(&self) -> bool {
+        self.check()
+    }
+
+    fn clear(&mut self) {
+        HashDirty::clear(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Tracked for DirtyVec<T> {
+    fn dirty(&self) -> bool {
+        self.any_dirty()
+    }
+
+    fn clear(&mut self) {
+        self.clear_all()
+    }
+}
+
+/// Clearing flushes the dirty entries.
+#[cfg(feature = "std")]
+impl<K, V> Tracked for DirtyCache<K, V> where K: Eq + Hash + Clone {
+    fn dirty(&self) -> bool {
+        self.any_dirty()
+    }
+
+    fn clear(&mut self) {
+        self.flush_dirty();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V> Tracked for DirtyHashMap<K, V> where K: Eq + Hash + Clone {
+    fn dirty(&self) -> bool {
+        DirtyHashMap::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DirtyHashMap::clear(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Tracked for DirtyString {
+    fn dirty(&self) -> bool {
+        DirtyString::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DirtyString::clear(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Tracked for DirtyBuffer {
+    fn dirty(&self) -> bool {
+        DirtyBuffer::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        self.clear_ranges()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const PAGE: usize> Tracked for PagedDirty<PAGE> {
+    fn dirty(&self) -> bool {
+        PagedDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        PagedDirty::clear(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K, V> Tracked for DirtyBTreeMap<K, V> where K: Ord + Clone {
+    fn dirty(&self) -> bool {
+        DirtyBTreeMap::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DirtyBTreeMap::clear(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Tracked for DirtySlab<T> {
+    fn dirty(&self) -> bool {
+        DirtySlab::any_dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DirtySlab::clear_all(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Tracked for DirtyGrid<T> {
+    fn dirty(&self) -> bool {
+        DirtyGrid::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DirtyGrid::clear(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Tracked for HistoryDirty<T> where T: Clone {
+    fn dirty(&self) -> bool {
+        HistoryDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        HistoryDirty::clear(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, Op> Tracked for JournaledDirty<T, Op> where Op: Apply<T> {
+    fn dirty(&self) -> bool {
+        JournaledDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        JournaledDirty::clear(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Tracked for DirtyBox<T> {
+    fn dirty(&self) -> bool {
+        DirtyBox::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        DirtyBox::clear(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Tracked for DirtySlice<T> {
+    fn dirty(&self) -> bool {
+        self.any_dirty()
+    }
+
+    fn clear(&mut self) {
+        self.clear_all()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T> Tracked for WatchDirty<T> where T: Clone {
+    fn dirty(&self) -> bool {
+        WatchDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        WatchDirty::clear(self)
+    }
+}
+
+#[cfg(feature = "notify")]
+impl<T> Tracked for WatchedDirty<T> {
+    fn dirty(&self) -> bool {
+        WatchedDirty::dirty(self)
+    }
+
+    fn clear(&mut self) {
+        WatchedDirty::clear(self)
+    }
+}
@@ -0,0 +1,111 @@
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use flag::DirtyFlag;
+use Dirty;
+
+impl<T, D> Dirty<T, D> where T: Clone, D: DirtyFlag {
+    /// Begins a transaction on the value. Writes through the transaction
+    /// leave the dirty flag alone until commit(), which sets it once, while
+    /// rollback() or dropping the transaction restores the value.
+    pub fn begin(&mut self) -> Transaction<'_, T, D> {
+        Transaction {
+            old: Some(self.value.clone()),
+            dirty: self,
+            modified: false,
+        }
+    }
+}
+
+/// Transaction gives write access to the value of a Dirty which is undone
+/// unless committed, see Dirty::begin().
+pub struct Transaction<'a, T: 'a + Clone, D: 'a + DirtyFlag = bool> {
+    dirty: &'a mut Dirty<T, D>,
+    // The value before the transaction, None once committed or rolled back.
+    old: Option<T>,
+    modified: bool,
+}
+
+impl<'a, T, D> Transaction<'a, T, D> where T: Clone, D: DirtyFlag {
+    /// Returns true if the transaction has been mutably dereferenced.
+    pub fn modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Keeps the writes, setting the dirty flag if the transaction was
+    /// mutably dereferenced.
+    pub fn commit(mut self) {
+        self.old = None;
+        if self.modified {
+            self.dirty.mark();
+        }
+    }
+
+    /// Restores the value as it was before the transaction, leaving the dirty
+    /// flag as it was.
+    pub fn rollback(self) {}
+}
+
+impl<'a, T, D> Deref for Transaction<'a, T, D> where T: Clone, D: DirtyFlag {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.dirty.value
+    }
+}
+
+impl<'a, T, D> DerefMut for Transaction<'a, T, D> where T: Clone, D: DirtyFlag {
+    fn deref_mut(&mut self) -> &mut T {
+        self.modified = true;
+        &mut self.dirty.value
+    }
+}
+
+impl<'a, T, D> Drop for Transaction<'a, T, D> where T: Clone, D: DirtyFlag {
+    fn drop(&mut self) {
+        if let Some(old) = self.old.take() {
+            self.dirty.value = old;
+        }
+    }
+}
+
+impl<'a, T, D> fmt::Debug for Transaction<'a, T, D> where T: Clone + fmt::Debug, D: DirtyFlag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("value", &self.dirty.value)
+            .field("modified", &self.modified)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use Dirty;
+
+    #[test]
+    fn commit_marks_once() {
+        let mut dirty = Dirty::new_clean(1);
+        let mut tx = dirty.begin();
+        *tx += 1;
+        *tx *= 3;
+        assert!(tx.modified());
+        tx.commit();
+        assert!(dirty.dirty() && *dirty == 6);
+        dirty.clear();
+        dirty.begin().commit();
+        assert!(!dirty.dirty());
+    }
+
+    #[test]
+    fn rollback_restores() {
+        let mut dirty = Dirty::new_clean(1);
+        let mut tx = dirty.begin();
+        *tx = 5;
+        tx.rollback();
+        assert!(!dirty.dirty() && *dirty == 1);
+        {
+            let mut tx = dirty.begin();
+            *tx = 7;
+        }
+        assert!(!dirty.dirty() && *dirty == 1);
+    }
+}
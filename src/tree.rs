@@ -0,0 +1,206 @@
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+/// Identifies a node of a DirtyTree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// DirtyNode holds a value of type T in a DirtyTree with its own dirty flag
+/// and links to its parent and children.
+pub struct DirtyNode<T> {
+    value: T,
+    dirty: bool,
+    dirty_in_subtree: usize,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl<T> DirtyNode<T> {
+    /// Returns true if this node is dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns true if this node or any descendant is dirty, false otherwise.
+    pub fn subtree_dirty(&self) -> bool {
+        self.dirty_in_subtree > 0
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the parent node, if any.
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    /// Returns the child nodes.
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+impl<T> Deref for DirtyNode<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// DirtyTree holds a tree of DirtyNodes where writing a node sets its dirty
+/// flag and makes the subtree of every ancestor dirty, so interior nodes can
+/// tell whether anything beneath them needs work.
+pub struct DirtyTree<T> {
+    nodes: Vec<DirtyNode<T>>,
+}
+
+impl<T> DirtyTree<T> {
+    /// Create a new empty DirtyTree.
+    pub fn new() -> DirtyTree<T> {
+        DirtyTree {
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Adds a root node, which starts dirty.
+    pub fn add_root(&mut self, val: T) -> NodeId {
+        self.add(None, val)
+    }
+
+    /// Adds a child node to parent, which starts dirty.
+    pub fn add_child(&mut self, parent: NodeId, val: T) -> NodeId {
+        let id = self.add(Some(parent), val);
+        self.nodes[parent.0].children.push(id);
+        id
+    }
+
+    /// Returns the node for id.
+    pub fn node(&self, id: NodeId) -> &DirtyNode<T> {
+        &self.nodes[id.0]
+    }
+
+    /// Returns true if the node is dirty, false otherwise.
+    pub fn dirty(&self, id: NodeId) -> bool {
+        self.nodes[id.0].dirty
+    }
+
+    /// Returns true if the node or any descendant is dirty, false otherwise.
+    pub fn subtree_dirty(&self, id: NodeId) -> bool {
+        self.nodes[id.0].subtree_dirty()
+    }
+
+    /// Read the value of a node.
+    pub fn read(&self, id: NodeId) -> &T {
+        &self.nodes[id.0].value
+    }
+
+    /// Writable value return of a node, sets its dirty flag.
+    pub fn write(&mut self, id: NodeId) -> &mut T {
+        if !self.nodes[id.0].dirty {
+            self.nodes[id.0].dirty = true;
+            self.propagate(id, |count| *count += 1);
+        }
+        &mut self.nodes[id.0].value
+    }
+
+    /// Clears the dirty flag of a node.
+    pub fn clear(&mut self, id: NodeId) {
+        if self.nodes[id.0].dirty {
+            self.nodes[id.0].dirty = false;
+            self.propagate(id, |count| *count -= 1);
+        }
+    }
+
+    /// Clears the dirty flag of a node and all of its descendants.
+    pub fn clear_subtree(&mut self, id: NodeId) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            if !self.nodes[id.0].subtree_dirty() {
+                continue;
+            }
+            self.clear(id);
+            stack.extend_from_slice(&self.nodes[id.0].children);
+        }
+    }
+
+    fn add(&mut self, parent: Option<NodeId>, val: T) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(DirtyNode {
+            value: val,
+            dirty: true,
+            dirty_in_subtree: 0,
+            parent,
+            children: Vec::new(),
+        });
+        self.propagate(id, |count| *count += 1);
+        id
+    }
+
+    fn propagate<F>(&mut self, id: NodeId, f: F) where F: Fn(&mut usize) {
+        let mut next = Some(id);
+        while let Some(id) = next {
+            let node = &mut self.nodes[id.0];
+            f(&mut node.dirty_in_subtree);
+            next = node.parent;
+        }
+    }
+}
+
+impl<T> Default for DirtyTree<T> {
+    fn default() -> Self {
+        DirtyTree::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtyTree;
+
+    #[test]
+    fn new_nodes_dirty() {
+        let mut tree = DirtyTree::new();
+        let root = tree.add_root(0);
+        let child = tree.add_child(root, 1);
+        assert!(tree.dirty(root));
+        assert!(tree.dirty(child));
+        assert!(tree.node(child).parent() == Some(root));
+        assert!(tree.node(root).children() == [child]);
+    }
+
+    #[test]
+    fn write_bubbles_to_ancestors() {
+        let mut tree = DirtyTree::new();
+        let root = tree.add_root(0);
+        let a = tree.add_child(root, 1);
+        let b = tree.add_child(root, 2);
+        let leaf = tree.add_child(a, 3);
+        tree.clear_subtree(root);
+        assert!(!tree.subtree_dirty(root));
+        *tree.write(leaf) += 1;
+        assert!(tree.dirty(leaf));
+        assert!(!tree.dirty(a));
+        assert!(tree.subtree_dirty(a));
+        assert!(tree.subtree_dirty(root));
+        assert!(!tree.subtree_dirty(b));
+        assert!(*tree.read(leaf) == 4);
+    }
+
+    #[test]
+    fn clear_updates_ancestors() {
+        let mut tree = DirtyTree::new();
+        let root = tree.add_root(0);
+        let a = tree.add_child(root, 1);
+        let b = tree.add_child(a, 2);
+        tree.clear(root);
+        tree.clear(a);
+        assert!(tree.subtree_dirty(root));
+        tree.clear(b);
+        assert!(!tree.subtree_dirty(root));
+        tree.write(b);
+        tree.write(b);
+        tree.clear(b);
+        assert!(!tree.subtree_dirty(root));
+    }
+}
@@ -0,0 +1,192 @@
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Set in Shared::back when the back buffer holds a snapshot the reader has
+/// not taken yet.
+const FRESH: usize = 4;
+
+// Each of the three buffers is owned by exactly one of the writer (its
+// input), the reader (its output) or neither (back). Ownership only moves by
+// atomically swapping indices through back, so a buffer is never accessed by
+// both sides at once and neither side ever waits.
+struct Shared<T> {
+    buffers: [UnsafeCell<T>; 3],
+    // Index of the buffer owned by neither side, with FRESH.
+    back: AtomicUsize,
+}
+
+// SAFETY: a buffer is only accessed by the side owning its index, and the
+// AcqRel swaps of back order the accesses before and after a handover.
+// Snapshots move between threads, so T must be Send.
+unsafe impl<T> Sync for Shared<T> where T: Send {}
+
+/// TripleBufferDirty wraps a value of type T like Dirty and publishes
+/// snapshots of it to a TripleBufferReader through a triple buffer, for real
+/// time consumers which must never block.
+/// The buffer is lock-free and wait-free, publishing and reading are a clone
+/// or a borrow plus a single atomic swap, so neither side ever waits on the
+/// other.
+pub struct TripleBufferDirty<T> where T: Clone {
+    value: T,
+    dirty: bool,
+    input: usize,
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> TripleBufferDirty<T> where T: Clone {
+    /// Create a new dirty TripleBufferDirty and the reader of its snapshots.
+    pub fn new(val: T) -> (TripleBufferDirty<T>, TripleBufferReader<T>) {
+        let shared = Arc::new(Shared {
+            buffers: [UnsafeCell::new(val.clone()), UnsafeCell::new(val.clone()),
+                      UnsafeCell::new(val.clone())],
+            back: AtomicUsize::new(1),
+        });
+        let writer = TripleBufferDirty {
+            value: val,
+            dirty: true,
+            input: 0,
+            shared: shared.clone(),
+        };
+        (writer, TripleBufferReader { output: 2, shared })
+    }
+
+    /// Returns true if written since the last publish(), false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.value
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.dirty = true;
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Publishes a snapshot of the value to the reader if dirty, clearing the
+    /// dirty flag. An earlier snapshot not yet read is replaced. Returns true
+    /// if published.
+    pub fn publish(&mut self) -> bool {
+        if !self.dirty {
+            return false;
+        }
+        // SAFETY: the writer owns the input buffer until it is swapped into
+        // back below.
+        unsafe { &mut *self.shared.buffers[self.input].get() }.clone_from(&self.value);
+        let back = self.shared.back.swap(self.input | FRESH, Ordering::AcqRel);
+        self.input = back & !FRESH;
+        self.dirty = false;
+        true
+    }
+}
+
+impl<T> Deref for TripleBufferDirty<T> where T: Clone {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> fmt::Debug for TripleBufferDirty<T> where T: Clone + fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TripleBufferDirty")
+            .field("value", &self.value)
+            .field("dirty", &self.dirty)
+            .finish()
+    }
+}
+
+/// TripleBufferReader reads the snapshots published by a TripleBufferDirty
+/// wait-free, it may be sent to another thread.
+pub struct TripleBufferReader<T> {
+    output: usize,
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> TripleBufferReader<T> {
+    /// Returns true if a snapshot was published since the last read_dirty(),
+    /// false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.shared.back.load(Ordering::Acquire) & FRESH != 0
+    }
+
+    /// Read the latest snapshot taken by read_dirty().
+    pub fn read(&self) -> &T {
+        // SAFETY: the reader owns the output buffer, which only changes in
+        // read_dirty() taking &mut self.
+        unsafe { &*self.shared.buffers[self.output].get() }
+    }
+
+    /// Takes and reads the latest published snapshot only if published since
+    /// the last read_dirty().
+    pub fn read_dirty(&mut self) -> Option<&T> {
+        if !self.dirty() {
+            return None;
+        }
+        let back = self.shared.back.swap(self.output, Ordering::AcqRel);
+        self.output = back & !FRESH;
+        Some(self.read())
+    }
+}
+
+impl<T> fmt::Debug for TripleBufferReader<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TripleBufferReader")
+            .field("dirty", &self.dirty())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TripleBufferDirty;
+    use std::thread;
+
+    #[test]
+    fn publish_only_when_dirty() {
+        let (mut writer, mut reader) = TripleBufferDirty::new(0);
+        assert!(!reader.dirty());
+        assert!(writer.publish());
+        assert!(!writer.publish());
+        *writer.write() += 1;
+        writer.publish();
+        assert!(reader.read_dirty() == Some(&1));
+        assert!(reader.read_dirty().is_none());
+        assert!(*reader.read() == 1);
+        writer.set(5);
+        assert!(!reader.dirty());
+        writer.publish();
+        assert!(*reader.read_dirty().unwrap() == 5);
+    }
+
+    #[test]
+    fn reader_sees_latest() {
+        let (mut writer, mut reader) = TripleBufferDirty::new(0u32);
+        let handle = thread::spawn(move || {
+            let mut last = 0;
+            while last != 1000 {
+                if let Some(val) = reader.read_dirty() {
+                    assert!(*val >= last);
+                    last = *val;
+                }
+            }
+        });
+        for i in 1..=1000 {
+            writer.set(i);
+            writer.publish();
+        }
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+use core::fmt;
+use core::ops::Deref;
+
+/// ValidatedDirty wraps a value of type T like Dirty with a validator run
+/// before the dirty flag is cleared, so an invalid value is never marked as
+/// synced. clear() and flush() return the validator's error and leave the
+/// value dirty when it fails.
+pub struct ValidatedDirty<T, F> {
+    value: T,
+    dirty: bool,
+    validator: F,
+}
+
+impl<T, E, F> ValidatedDirty<T, F> where F: Fn(&T) -> Result<(), E> {
+    /// Create a new ValidatedDirty.
+    pub fn new(val: T, validator: F) -> ValidatedDirty<T, F> {
+        ValidatedDirty {
+            value: val,
+            dirty: true,
+            validator,
+        }
+    }
+
+    /// Create a new clean ValidatedDirty.
+    pub fn new_clean(val: T, validator: F) -> ValidatedDirty<T, F> {
+        ValidatedDirty {
+            value: val,
+            dirty: false,
+            validator,
+        }
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.value
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.dirty = true;
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Runs the validator on the value.
+    pub fn validate(&self) -> Result<(), E> {
+        (self.validator)(&self.value)
+    }
+
+    /// Clears the dirty flag if the value is valid, otherwise returns the
+    /// validator's error leaving the value dirty.
+    pub fn clear(&mut self) -> Result<(), E> {
+        self.validate()?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Calls f with the value if dirty and valid, clearing the dirty flag only
+    /// if f returns Ok. Returns true if flushed, false if already clean.
+    pub fn flush<G>(&mut self, f: G) -> Result<bool, E>
+        where G: FnOnce(&T) -> Result<(), E>
+    {
+        if !self.dirty {
+            return Ok(false);
+        }
+        self.validate()?;
+        f(&self.value)?;
+        self.dirty = false;
+        Ok(true)
+    }
+
+    /// Consumes the ValidatedDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, F> Deref for ValidatedDirty<T, F> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, F> fmt::Debug for ValidatedDirty<T, F> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ValidatedDirty")
+            .field("value", &self.value)
+            .field("dirty", &self.dirty)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValidatedDirty;
+
+    fn port(val: &u32) -> Result<(), &'static str> {
+        match *val {
+            1..=65535 => Ok(()),
+            _ => Err("port out of range"),
+        }
+    }
+
+    #[test]
+    fn clear_refuses_invalid() {
+        let mut config = ValidatedDirty::new_clean(80, port);
+        config.set(0);
+        assert!(config.clear() == Err("port out of range"));
+        assert!(config.dirty());
+        config.set(8080);
+        assert!(config.clear() == Ok(()));
+        assert!(!config.dirty());
+    }
+
+    #[test]
+    fn flush_validates_first() {
+        let mut config = ValidatedDirty::new(70000, port);
+        let mut saved = None;
+        assert!(config.flush(|v| { saved = Some(*v); Ok(()) }) == Err("port out of range"));
+        assert!(saved.is_none() && config.dirty());
+        config.set(443);
+        assert!(config.flush(|v| { saved = Some(*v); Ok(()) }) == Ok(true));
+        assert!(saved == Some(443) && !config.dirty());
+        assert!(config.flush(|_| Ok(())) == Ok(false));
+    }
+}
@@ -0,0 +1,386 @@
+#[cfg(feature = "bytemuck")]
+use bytemuck::Pod;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::{Deref, Index, IndexMut};
+
+#[cfg(feature = "bytemuck")]
+use pod::DirtyBytes;
+
+/// DirtyVec wraps a `Vec<T>` with a dirty flag per element, set when the
+/// element at an index is written or shifted by insert() or remove(), reset on
+/// clear() or clear_all().
+/// Use deref (`dirty_vec[i]`) to read elements, a mutable index marks the
+/// element dirty.
+/// A DirtyVec made with with_splice_ops() also records the mutations as
+/// splice operations, see splice_ops().
+pub struct DirtyVec<T> {
+    values: Vec<T>,
+    dirty: Vec<bool>,
+    ops: Option<Vec<SpliceOp>>,
+}
+
+/// A change to a run of elements of a DirtyVec, returned in order by
+/// splice_ops(). Indices are into the vector as it was after the previous
+/// operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpliceOp {
+    /// len elements were inserted at index.
+    Insert { index: usize, len: usize },
+    /// len elements were removed at index.
+    Remove { index: usize, len: usize },
+    /// len elements from index were written in place.
+    Replace { index: usize, len: usize },
+}
+
+impl<T> DirtyVec<T> {
+    /// Create a new empty DirtyVec.
+    pub fn new() -> DirtyVec<T> {
+        DirtyVec {
+            values: Vec::new(),
+            dirty: Vec::new(),
+            ops: None,
+        }
+    }
+
+    /// Create a new empty DirtyVec recording its mutations as splice
+    /// operations.
+    pub fn with_splice_ops() -> DirtyVec<T> {
+        DirtyVec {
+            values: Vec::new(),
+            dirty: Vec::new(),
+            ops: Some(Vec::new()),
+        }
+    }
+
+    /// Returns true if the element at index is dirty, false otherwise.
+    pub fn dirty(&self, index: usize) -> bool {
+        self.dirty.get(index).cloned().unwrap_or(false)
+    }
+
+    /// Returns true if any element is dirty, false otherwise.
+    pub fn any_dirty(&self) -> bool {
+        self.dirty.iter().any(|&d| d)
+    }
+
+    /// Writable element return, sets the element's dirty flag.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.values.len() {
+            return None;
+        }
+        self.dirty[index] = true;
+        self.record(SpliceOp::Replace { index, len: 1 });
+        self.values.get_mut(index)
+    }
+
+    /// Appends an element, which starts dirty.
+    pub fn push(&mut self, val: T) {
+        self.record(SpliceOp::Insert { index: self.values.len(), len: 1 });
+        self.values.push(val);
+        self.dirty.push(true);
+    }
+
+    /// Removes the last element and returns it.
+    pub fn pop(&mut self) -> Option<T> {
+        let val = self.values.pop()?;
+        self.dirty.pop();
+        self.record(SpliceOp::Remove { index: self.values.len(), len: 1 });
+        Some(val)
+    }
+
+    /// Inserts an element at index, marking it and every following element
+    /// dirty as they shift.
+    pub fn insert(&mut self, index: usize, val: T) {
+        self.values.insert(index, val);
+        self.dirty.insert(index, true);
+        self.mark_from(index);
+        self.record(SpliceOp::Insert { index, len: 1 });
+    }
+
+    /// Removes the element at index, marking every following element dirty as
+    /// they shift.
+    pub fn remove(&mut self, index: usize) -> T {
+        let val = self.values.remove(index);
+        self.dirty.remove(index);
+        self.mark_from(index);
+        self.record(SpliceOp::Remove { index, len: 1 });
+        val
+    }
+
+    /// Clears the dirty flag of the element at index.
+    pub fn clear(&mut self, index: usize) {
+        if let Some(d) = self.dirty.get_mut(index) {
+            *d = false;
+        }
+    }
+
+    /// Clears the dirty flag of every element.
+    pub fn clear_all(&mut self) {
+        for d in self.dirty.iter_mut() {
+            *d = false;
+        }
+    }
+
+    /// Returns the operations changing the vector since the last
+    /// take_splice_ops(), with consecutive operations on adjacent elements
+    /// merged. Empty unless made with with_splice_ops().
+    /// The operations are kept apart from the dirty flags, clearing flags
+    /// leaves them in place.
+    pub fn splice_ops(&self) -> &[SpliceOp] {
+        match self.ops {
+            Some(ref ops) => ops,
+            None => &[],
+        }
+    }
+
+    /// Returns the recorded splice operations, leaving none recorded.
+    pub fn take_splice_ops(&mut self) -> Vec<SpliceOp> {
+        match self.ops {
+            Some(ref mut ops) => mem::take(ops),
+            None => Vec::new(),
+        }
+    }
+
+    /// Iterate over the dirty elements and their indices.
+    pub fn iter_dirty(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.values.iter()
+            .zip(self.dirty.iter())
+            .enumerate()
+            .filter(|&(_, (_, &d))| d)
+            .map(|(i, (val, _))| (i, val))
+    }
+
+    /// Returns the indices of the dirty elements, clearing their flags.
+    pub fn drain_dirty_indices(&mut self) -> Vec<usize> {
+        let indices = self.dirty.iter()
+            .enumerate()
+            .filter(|&(_, &d)| d)
+            .map(|(i, _)| i)
+            .collect();
+        self.clear_all();
+        indices
+    }
+
+    /// Consumes the DirtyVec, returning the inner Vec.
+    pub fn into_inner(self) -> Vec<T> {
+        self.values
+    }
+
+    fn record(&mut self, op: SpliceOp) {
+        if let Some(ref mut ops) = self.ops {
+            record(ops, op);
+        }
+    }
+
+    fn mark_from(&mut self, index: usize) {
+        for d in self.dirty[index..].iter_mut() {
+            *d = true;
+        }
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<T> DirtyVec<T> where T: Pod {
+    /// Iterate over the byte offsets and bytes of each run of consecutive
+    /// dirty elements.
+    pub fn dirty_bytes(&self) -> impl Iterator<Item = (usize, &[u8])> {
+        DirtyBytes::new(&self.values, self.dirty.iter().enumerate().filter(|&(_, &d)| d).map(|(i, _)| i))
+    }
+}
+
+/// Appends op to ops, merging it into the last operation where the two touch
+/// adjacent elements.
+fn record(ops: &mut Vec<SpliceOp>, op: SpliceOp) {
+    use self::SpliceOp::*;
+    let merged = match (ops.last_mut(), op) {
+        (Some(&mut Insert { index, ref mut len }), Insert { index: i, len: n })
+            if i >= index && i <= index + *len => {
+            *len += n;
+            true
+        },
+        // Writing an element just inserted is covered by the insert.
+        (Some(&mut Insert { index, len }), Replace { index: i, len: n })
+            if i >= index && i + n <= index + len => true,
+        (Some(&mut Remove { ref mut index, ref mut len }), Remove { index: i, len: n })
+            if i == *index || i + n == *index => {
+            *index = i;
+            *len += n;
+            true
+        },
+        (Some(&mut Replace { ref mut index, ref mut len }), Replace { index: i, len: n })
+            if i + n >= *index && i <= *index + *len => {
+            let end = (*index + *len).max(i + n);
+            *index = (*index).min(i);
+            *len = end - *index;
+            true
+        },
+        _ => false,
+    };
+    if !merged {
+        ops.push(op);
+    }
+}
+
+impl<T> From<Vec<T>> for DirtyVec<T> {
+    /// Create a new DirtyVec with every element dirty.
+    fn from(values: Vec<T>) -> DirtyVec<T> {
+        DirtyVec {
+            dirty: vec![true; values.len()],
+            values,
+            ops: None,
+        }
+    }
+}
+
+impl<T> Deref for DirtyVec<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        &self.values
+    }
+}
+
+impl<T> Index<usize> for DirtyVec<T> {
+    type Output = T;
+    fn index(&self, index: usize) -> &T {
+        &self.values[index]
+    }
+}
+
+impl<T> IndexMut<usize> for DirtyVec<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.dirty[index] = true;
+        self.record(SpliceOp::Replace { index, len: 1 });
+        &mut self.values[index]
+    }
+}
+
+impl<T> Default for DirtyVec<T> {
+    fn default() -> Self {
+        DirtyVec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirtyVec;
+    use alloc::vec::Vec;
+
+    fn clean(values: Vec<i32>) -> DirtyVec<i32> {
+        let mut vec = DirtyVec::from(values);
+        vec.clear_all();
+        vec
+    }
+
+    #[test]
+    fn push_is_dirty() {
+        let mut vec = DirtyVec::new();
+        assert!(!vec.any_dirty());
+        vec.push(0);
+        assert!(vec.dirty(0));
+        assert!(!vec.dirty(1));
+    }
+
+    #[test]
+    fn index_mut_marks_element() {
+        let mut vec = clean(vec![0, 1, 2]);
+        vec[1] = 5;
+        assert!(!vec.dirty(0));
+        assert!(vec.dirty(1));
+        assert!(!vec.dirty(2));
+        assert!(vec[1] == 5);
+        *vec.get_mut(2).unwrap() += 1;
+        assert!(vec.dirty(2));
+        assert!(vec.get_mut(3).is_none());
+    }
+
+    #[test]
+    fn insert_remove_mark_shifted() {
+        let mut vec = clean(vec![0, 1, 2, 3]);
+        vec.insert(2, 9);
+        assert!(vec.drain_dirty_indices() == vec![2, 3, 4]);
+        assert!(vec.remove(3) == 2);
+        assert!(vec.drain_dirty_indices() == vec![3]);
+        assert!(*vec == [0, 1, 9, 3][..]);
+    }
+
+    #[test]
+    fn iter_dirty() {
+        let mut vec = clean(vec![0, 1, 2]);
+        vec[0] = 4;
+        vec[2] = 6;
+        let dirty: Vec<(usize, &i32)> = vec.iter_dirty().collect();
+        assert!(dirty == vec![(0, &4), (2, &6)]);
+        vec.clear(0);
+        assert!(vec.drain_dirty_indices() == vec![2]);
+        assert!(!vec.any_dirty());
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn dirty_bytes() {
+        let mut vec = clean(vec![0, 1, 2, 3]);
+        vec[1] = 7;
+        vec[3] = 9;
+        let runs: Vec<(usize, &[u8])> = vec.dirty_bytes().collect();
+        assert!(runs == [(4, &7i32.to_ne_bytes()[..]), (12, &9i32.to_ne_bytes()[..])]);
+    }
+
+    fn recording(values: Vec<i32>) -> DirtyVec<i32> {
+        let mut vec = DirtyVec::with_splice_ops();
+        for val in values {
+            vec.push(val);
+        }
+        vec.take_splice_ops();
+        vec.clear_all();
+        vec
+    }
+
+    #[test]
+    fn splice_ops_merge_adjacent() {
+        use super::SpliceOp::*;
+        let mut vec = recording(vec![0, 1, 2, 3, 4]);
+        vec[1] = 5;
+        vec[2] = 6;
+        vec[1] = 7;
+        vec.push(8);
+        vec.push(9);
+        vec[6] = 10;
+        vec.remove(3);
+        vec.remove(2);
+        assert!(vec.splice_ops() == [
+            Replace { index: 1, len: 2 },
+            Insert { index: 5, len: 2 },
+            Remove { index: 2, len: 2 },
+        ]);
+        assert!(*vec == [0, 7, 4, 8, 10][..]);
+        assert!(vec.take_splice_ops().len() == 3);
+        assert!(vec.splice_ops().is_empty());
+        assert!(vec.pop() == Some(10) && vec.splice_ops() == [Remove { index: 4, len: 1 }]);
+    }
+
+    #[test]
+    fn splice_ops_outlive_flag_clears() {
+        use super::SpliceOp::*;
+        let mut vec = recording(vec![0, 1, 2]);
+        vec[0] = 3;
+        vec.clear(0);
+        vec[2] = 4;
+        assert!(vec.drain_dirty_indices() == vec![2]);
+        vec.insert(1, 5);
+        vec.clear_all();
+        assert!(!vec.any_dirty());
+        assert!(vec.take_splice_ops() == [
+            Replace { index: 0, len: 1 },
+            Replace { index: 2, len: 1 },
+            Insert { index: 1, len: 1 },
+        ]);
+    }
+
+    #[test]
+    fn splice_ops_opt_in() {
+        let mut vec = DirtyVec::from(vec![1, 2]);
+        vec.push(3);
+        vec[0] = 4;
+        assert!(vec.splice_ops().is_empty() && vec.take_splice_ops().is_empty());
+    }
+}
@@ -0,0 +1,171 @@
+use core::fmt;
+use core::ops::Deref;
+
+/// VersionedDirty wraps a value of type T with a version counter bumped on
+/// every write(), in place of a dirty flag.
+/// Any number of consumers may each keep the last version they have seen, or a
+/// ReaderToken from reader(), and ask whether the value changed since without
+/// a shared clear().
+pub struct VersionedDirty<T> {
+    value: T,
+    version: u64,
+}
+
+impl<T> VersionedDirty<T> {
+    /// Create a new VersionedDirty at version 1, so it has changed since a
+    /// last seen version of 0.
+    pub fn new(val: T) -> VersionedDirty<T> {
+        VersionedDirty {
+            value: val,
+            version: 1,
+        }
+    }
+
+    /// Returns the current version.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns true if the value was written since version, false otherwise.
+    pub fn changed_since(&self, version: u64) -> bool {
+        self.version > version
+    }
+
+    /// Writable value return, bumps the version.
+    pub fn write(&mut self) -> &mut T {
+        self.version += 1;
+        &mut self.value
+    }
+
+    /// Set the value, bumps the version.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.version += 1;
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Read the value only if written since last_seen, updating last_seen to
+    /// the current version.
+    pub fn read_if_changed(&self, last_seen: &mut u64) -> Option<&T> {
+        match self.changed_since(*last_seen) {
+            true => {
+                *last_seen = self.version;
+                Some(&self.value)
+            },
+            false => None,
+        }
+    }
+
+    /// Create a ReaderToken for a new consumer, which sees the value as dirty
+    /// until it is read or cleared through the token.
+    pub fn reader(&self) -> ReaderToken {
+        ReaderToken {
+            last_seen: 0,
+        }
+    }
+
+    /// Returns true if dirty for the consumer holding token, false otherwise.
+    pub fn dirty(&self, token: &ReaderToken) -> bool {
+        self.changed_since(token.last_seen)
+    }
+
+    /// Clears the dirty flag of the consumer holding token.
+    pub fn clear(&self, token: &mut ReaderToken) {
+        token.last_seen = self.version;
+    }
+
+    /// Read the value only if modified since the consumer holding token last
+    /// read or cleared it, clearing the dirty flag of that consumer.
+    pub fn read_dirty(&self, token: &mut ReaderToken) -> Option<&T> {
+        self.read_if_changed(&mut token.last_seen)
+    }
+
+    /// Consumes the VersionedDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// ReaderToken holds the last version of a VersionedDirty seen by one
+/// consumer, giving each consumer its own dirty flag.
+/// A token should only be used with the VersionedDirty that created it.
+#[derive(Clone, Debug)]
+pub struct ReaderToken {
+    last_seen: u64,
+}
+
+impl<T> Deref for VersionedDirty<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Default for VersionedDirty<T> where T: Default {
+    fn default() -> Self {
+        VersionedDirty::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for VersionedDirty<T> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VersionedDirty")
+            .field("value", &self.value)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VersionedDirty;
+
+    #[test]
+    fn new_changed_since_zero() {
+        let dirty = VersionedDirty::new(0);
+        assert!(dirty.version() == 1);
+        assert!(dirty.changed_since(0));
+        assert!(!dirty.changed_since(1));
+    }
+
+    #[test]
+    fn write_bumps_version() {
+        let mut dirty = VersionedDirty::new(0);
+        *dirty.write() += 1;
+        dirty.set(5);
+        assert!(dirty.version() == 3);
+        assert!(*dirty == 5);
+    }
+
+    #[test]
+    fn independent_observers() {
+        let mut dirty = VersionedDirty::new(0);
+        let mut a = 0;
+        let mut b = 0;
+        assert!(dirty.read_if_changed(&mut a) == Some(&0));
+        assert!(dirty.read_if_changed(&mut a).is_none());
+        *dirty.write() += 1;
+        assert!(dirty.read_if_changed(&mut a) == Some(&1));
+        assert!(dirty.read_if_changed(&mut b) == Some(&1));
+        assert!(a == b && b == dirty.version());
+    }
+
+    #[test]
+    fn reader_tokens_are_independent() {
+        let mut dirty = VersionedDirty::new(0);
+        let mut renderer = dirty.reader();
+        let mut network = dirty.reader();
+        assert!(dirty.read_dirty(&mut renderer) == Some(&0));
+        assert!(!dirty.dirty(&renderer));
+        assert!(dirty.dirty(&network));
+        *dirty.write() += 1;
+        dirty.clear(&mut network);
+        assert!(!dirty.dirty(&network));
+        assert!(dirty.read_dirty(&mut renderer) == Some(&1));
+        assert!(dirty.read_dirty(&mut renderer).is_none());
+    }
+}
@@ -0,0 +1,80 @@
+use flag::DirtyFlag;
+use Dirty;
+
+/// DirtyView is a read only view of one or more dirty values yielding items
+/// of type T, such as the inputs of a derived computation, see zip().
+pub trait DirtyView<T> {
+    /// Returns true if dirty, false otherwise.
+    fn dirty(&self) -> bool;
+
+    /// Read the view.
+    fn read(&self) -> T;
+
+    /// Read the view only if dirty. The dirty flags are left set, clear the
+    /// inputs once the view has been consumed.
+    fn read_dirty(&self) -> Option<T> {
+        match self.dirty() {
+            true => Some(self.read()),
+            false => None,
+        }
+    }
+}
+
+impl<'a, T, D> DirtyView<&'a T> for &'a Dirty<T, D> where T: ?Sized, D: DirtyFlag {
+    fn dirty(&self) -> bool {
+        Dirty::dirty(self)
+    }
+
+    fn read(&self) -> &'a T {
+        Dirty::read(self)
+    }
+}
+
+/// Zip combines two views into a view of both, dirty if either is.
+#[derive(Clone, Copy, Debug)]
+pub struct Zip<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B, TA, TB> DirtyView<(TA, TB)> for Zip<A, B> where A: DirtyView<TA>, B: DirtyView<TB> {
+    fn dirty(&self) -> bool {
+        self.a.dirty() || self.b.dirty()
+    }
+
+    fn read(&self) -> (TA, TB) {
+        (self.a.read(), self.b.read())
+    }
+}
+
+/// Combines two views, such as references to Dirty values, into a view
+/// reading both which is dirty if either is.
+pub fn zip<A, B>(a: A, b: B) -> Zip<A, B> {
+    Zip { a, b }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{zip, DirtyView};
+    use Dirty;
+
+    #[test]
+    fn dirty_if_either() {
+        let mut a = Dirty::new_clean(1);
+        let b = Dirty::new_clean("b");
+        assert!(zip(&a, &b).read_dirty().is_none());
+        a.set(2);
+        let view = zip(&a, &b);
+        assert!(view.dirty());
+        assert!(view.read_dirty() == Some((&2, &"b")));
+    }
+
+    #[test]
+    fn nested() {
+        let a = Dirty::new_clean(1);
+        let b = Dirty::new_clean(2);
+        let c = Dirty::new(3);
+        let ((x, y), z) = zip(zip(&a, &b), &c).read_dirty().unwrap();
+        assert!(*x + *y + *z == 6);
+    }
+}
@@ -0,0 +1,115 @@
+use core::ops::Deref;
+use tokio::sync::watch;
+
+use Dirty;
+
+/// WatchDirty wraps a value of type T like Dirty and publishes it into a
+/// tokio watch channel on clear(), so async consumers may await
+/// Receiver::changed() while producers keep the write() and clear() API.
+pub struct WatchDirty<T> where T: Clone {
+    value: T,
+    dirty: bool,
+    sender: watch::Sender<T>,
+}
+
+impl<T> WatchDirty<T> where T: Clone {
+    /// Create a new WatchDirty with a watch channel holding val.
+    pub fn new(val: T) -> WatchDirty<T> {
+        let (sender, _) = watch::channel(val.clone());
+        WatchDirty {
+            value: val,
+            dirty: true,
+            sender,
+        }
+    }
+
+    /// Returns a new receiver of the published values.
+    pub fn subscribe(&self) -> watch::Receiver<T> {
+        self.sender.subscribe()
+    }
+
+    /// Returns true if dirty, false otherwise.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.value
+    }
+
+    /// Set the value, sets the dirty flag.
+    pub fn set(&mut self, val: T) {
+        self.value = val;
+        self.dirty = true;
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        &self.value
+    }
+
+    /// Publishes the value to the receivers if dirty, clearing the dirty flag.
+    pub fn clear(&mut self) {
+        if self.dirty {
+            self.sender.send_replace(self.value.clone());
+            self.dirty = false;
+        }
+    }
+
+    /// Consumes the WatchDirty, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for WatchDirty<T> where T: Clone {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Default for WatchDirty<T> where T: Clone + Default {
+    fn default() -> Self {
+        WatchDirty::new(T::default())
+    }
+}
+
+impl<T> Dirty<T> where T: Clone {
+    /// Converts into a WatchDirty keeping the dirty flag.
+    pub fn into_watch(self) -> WatchDirty<T> {
+        let (val, dirty) = self.into_parts();
+        let mut watched = WatchDirty::new(val);
+        watched.dirty = dirty;
+        watched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WatchDirty;
+    use Dirty;
+
+    #[test]
+    fn clear_publishes() {
+        let mut dirty = WatchDirty::new(1);
+        let mut rx = dirty.subscribe();
+        assert!(!rx.has_changed().unwrap());
+        *dirty.write() += 1;
+        assert!(!rx.has_changed().unwrap());
+        dirty.clear();
+        assert!(rx.has_changed().unwrap());
+        assert!(*rx.borrow_and_update() == 2);
+        dirty.clear();
+        assert!(!rx.has_changed().unwrap());
+    }
+
+    #[test]
+    fn into_watch_keeps_flag() {
+        let watched = Dirty::new_clean(1).into_watch();
+        assert!(!watched.dirty());
+        assert!(*watched.subscribe().borrow() == 1);
+    }
+}
@@ -0,0 +1,173 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::boxed::Box;
+use std::fmt;
+use std::io;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use Dirty;
+
+type Reload<T> = Box<dyn FnMut(&Path) -> io::Result<T>>;
+
+/// WatchedDirty wraps a value of type T like Dirty registered against a file
+/// path, so edits to the file made outside the program set the dirty flag on
+/// the next poll(), optionally reloading the value.
+/// The parent directory is watched so files replaced by a rename, as many
+/// editors save, are still seen. Saves made by the program itself are seen as
+/// edits too.
+pub struct WatchedDirty<T> {
+    value: Dirty<T>,
+    path: PathBuf,
+    changed: Arc<AtomicBool>,
+    reload: Option<Reload<T>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl<T> WatchedDirty<T> {
+    /// Create a new clean WatchedDirty watching the file at path.
+    pub fn watch<P: Into<PathBuf>>(val: T, path: P) -> notify::Result<WatchedDirty<T>> {
+        let path = path.into();
+        let changed = Arc::new(AtomicBool::new(false));
+        let flag = changed.clone();
+        let name = path.file_name().map(|name| name.to_os_string());
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            if let Ok(event) = res {
+                let edited = !event.kind.is_access() &&
+                    event.paths.iter().any(|p| p.file_name() == name.as_deref());
+                if edited {
+                    flag.store(true, Ordering::Release);
+                }
+            }
+        })?;
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        Ok(WatchedDirty {
+            value: Dirty::new_clean(val),
+            path,
+            changed,
+            reload: None,
+            _watcher: watcher,
+        })
+    }
+
+    /// Reloads the value with f when poll() sees an edit.
+    pub fn with_reload<F>(mut self, f: F) -> WatchedDirty<T>
+        where F: FnMut(&Path) -> io::Result<T> + 'static
+    {
+        self.reload = Some(Box::new(f));
+        self
+    }
+
+    /// Returns the path of the watched file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Applies edits to the file seen since the last poll, reloading the value
+    /// if a reload was given, and sets the dirty flag. Returns true if the
+    /// file was edited. If the reload fails the edit is kept pending for the
+    /// next poll and the error returned.
+    pub fn poll(&mut self) -> io::Result<bool> {
+        if !self.changed.swap(false, Ordering::AcqRel) {
+            return Ok(false);
+        }
+        match self.reload {
+            Some(ref mut reload) => match reload(&self.path) {
+                Ok(val) => self.value.set(val),
+                Err(err) => {
+                    self.changed.store(true, Ordering::Release);
+                    return Err(err);
+                },
+            },
+            None => {
+                self.value.write();
+            },
+        }
+        Ok(true)
+    }
+
+    /// Returns true if dirty, false otherwise. Edits to the file are only
+    /// seen after poll().
+    pub fn dirty(&self) -> bool {
+        self.value.dirty()
+    }
+
+    /// Writable value return, sets the dirty flag.
+    pub fn write(&mut self) -> &mut T {
+        self.value.write()
+    }
+
+    /// Read the value.
+    pub fn read(&self) -> &T {
+        self.value.read()
+    }
+
+    /// Clears the dirty flag.
+    pub fn clear(&mut self) {
+        self.value.clear();
+    }
+
+    /// Consumes the WatchedDirty, stopping the watch and returning the inner
+    /// value.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T> Deref for WatchedDirty<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value.read()
+    }
+}
+
+impl<T> fmt::Debug for WatchedDirty<T> where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WatchedDirty")
+            .field("value", self.value.read())
+            .field("dirty", &self.value.dirty())
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WatchedDirty;
+    use std::env;
+    use std::fs;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn external_edit_reloads() {
+        let dir = env::temp_dir().join(std::format!("dirty-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.txt");
+        fs::write(&path, "1").unwrap();
+        let mut config = WatchedDirty::watch(1u32, &path).unwrap()
+            .with_reload(|path| {
+                let text = fs::read_to_string(path)?;
+                text.trim().parse().map_err(|_| std::io::ErrorKind::InvalidData.into())
+            });
+        assert!(!config.poll().unwrap() && !config.dirty());
+        fs::write(&path, "2").unwrap();
+        let mut edited = false;
+        for _ in 0..200 {
+            thread::sleep(Duration::from_millis(10));
+            if let Ok(true) = config.poll() {
+                edited = true;
+            }
+            if edited && *config == 2 {
+                break;
+            }
+        }
+        assert!(edited && config.dirty() && *config == 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}